@@ -0,0 +1,69 @@
+use crate::{Coordinate, Point};
+
+/// An axis aligned box including both of its corners
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox<T> {
+    pub min: Point<T>,
+    pub max: Point<T>,
+}
+
+impl<T: Coordinate> BoundingBox<T> {
+    /// Builds the box spanned by two opposite corners, in any order
+    pub fn new(first: Point<T>, second: Point<T>) -> Self {
+        Self {
+            min: Point::new(
+                std::cmp::min(first.x, second.x),
+                std::cmp::min(first.y, second.y),
+            ),
+            max: Point::new(
+                std::cmp::max(first.x, second.x),
+                std::cmp::max(first.y, second.y),
+            ),
+        }
+    }
+
+    /// The smallest box containing every point, `None` if there aren't any
+    pub fn from_points<I: IntoIterator<Item = Point<T>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |bounds, point| {
+            bounds.union(&Self::new(point, point))
+        }))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Point::new(
+                std::cmp::min(self.min.x, other.min.x),
+                std::cmp::min(self.min.y, other.min.y),
+            ),
+            max: Point::new(
+                std::cmp::max(self.max.x, other.max.x),
+                std::cmp::max(self.max.y, other.max.y),
+            ),
+        }
+    }
+
+    pub fn contains(&self, point: &Point<T>) -> bool {
+        (self.min.x <= point.x && point.x <= self.max.x)
+            && (self.min.y <= point.y && point.y <= self.max.y)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Number of columns covered, counting both edges
+    pub fn width(&self) -> T {
+        self.max.x - self.min.x + T::ONE
+    }
+
+    /// Number of rows covered, counting both edges
+    pub fn height(&self) -> T {
+        self.max.y - self.min.y + T::ONE
+    }
+}
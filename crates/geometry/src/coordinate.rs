@@ -0,0 +1,65 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::str::FromStr;
+
+/// An integer type usable as a grid coordinate
+pub trait Coordinate:
+    Copy
+    + Default
+    + Debug
+    + Ord
+    + Hash
+    + FromStr
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Distance between two coordinates that can't underflow for unsigned types
+    fn abs_diff(self, other: Self) -> Self;
+}
+
+/// A coordinate that can point in either direction, which is needed to step along lines
+pub trait SignedCoordinate: Coordinate {
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+}
+
+macro_rules! impl_coordinate {
+    ($($ty:ty),*) => {
+        $(
+            impl Coordinate for $ty {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn abs_diff(self, other: Self) -> Self {
+                    std::cmp::max(self, other) - std::cmp::min(self, other)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_coordinate {
+    ($($ty:ty),*) => {
+        $(
+            impl SignedCoordinate for $ty {
+                fn abs(self) -> Self {
+                    <$ty>::abs(self)
+                }
+
+                fn signum(self) -> Self {
+                    <$ty>::signum(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_coordinate!(i32, i64, isize, u32, u64, usize);
+impl_signed_coordinate!(i32, i64, isize);
@@ -0,0 +1,11 @@
+//! Points, lines and bounding boxes on an integer grid, shared between the days
+
+mod bounding_box;
+mod coordinate;
+mod line;
+mod point;
+
+pub use bounding_box::BoundingBox;
+pub use coordinate::{Coordinate, SignedCoordinate};
+pub use line::{Line, LineIterator};
+pub use point::Point;
@@ -0,0 +1,298 @@
+use crate::{BoundingBox, Coordinate, Point, SignedCoordinate};
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Line<T> {
+    pub start: Point<T>,
+    pub end: Point<T>,
+}
+
+impl<T: Coordinate> Line<T> {
+    pub fn new(start: Point<T>, end: Point<T>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_axis_aligned(&self) -> bool {
+        self.start.x == self.end.x || self.start.y == self.end.y
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.start.x.abs_diff(self.end.x) == self.start.y.abs_diff(self.end.y)
+    }
+
+    /// Number of steps of `direction` it takes to get from `start` to `end`
+    pub fn steps(&self) -> T {
+        self.start.chebyshev_distance(&self.end)
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox<T> {
+        BoundingBox::new(self.start, self.end)
+    }
+}
+
+impl<T: SignedCoordinate> Line<T> {
+    pub fn direction(&self) -> Point<T> {
+        (self.end - self.start).direction()
+    }
+
+    /// Whether `point` lies exactly on the line
+    pub fn contains(&self, point: &Point<T>) -> bool {
+        (*point - self.start).cross(&(self.end - self.start)) == T::ZERO
+            && self.bounding_box().contains(point)
+    }
+
+    /// All lattice points shared by two horizontal, vertical or 45 degree lines, other slopes
+    /// have to be rasterized
+    pub fn intersections(&self, other: &Self) -> Vec<Point<T>> {
+        // A single point line has no direction to step along
+        if self.start == self.end {
+            return if other.contains(&self.start) {
+                vec![self.start]
+            } else {
+                vec![]
+            };
+        }
+        if other.start == other.end {
+            return if self.contains(&other.start) {
+                vec![other.start]
+            } else {
+                vec![]
+            };
+        }
+
+        let direction = self.direction();
+        let other_direction = other.direction();
+        let offset = other.start - self.start;
+        let denominator = direction.cross(&other_direction);
+
+        if denominator == T::ZERO {
+            // Parallel, so they only share points when they're also collinear
+            if offset.cross(&direction) != T::ZERO {
+                return vec![];
+            }
+            let length = direction.dot(&direction);
+            let other_start = offset.dot(&direction) / length;
+            let other_end = (other.end - self.start).dot(&direction) / length;
+            let from = std::cmp::max(T::ZERO, std::cmp::min(other_start, other_end));
+            let to = std::cmp::min(self.steps(), std::cmp::max(other_start, other_end));
+
+            let mut points = vec![];
+            let mut step = from;
+            while step <= to {
+                points.push(self.start + direction.scale(step));
+                step = step + T::ONE;
+            }
+            return points;
+        }
+
+        let step = offset.cross(&other_direction);
+        let other_step = offset.cross(&direction);
+        if step % denominator != T::ZERO || other_step % denominator != T::ZERO {
+            // Diagonals crossing between lattice points
+            return vec![];
+        }
+        let (step, other_step) = (step / denominator, other_step / denominator);
+        if !(T::ZERO..=self.steps()).contains(&step)
+            || !(T::ZERO..=other.steps()).contains(&other_step)
+        {
+            return vec![];
+        }
+
+        vec![self.start + direction.scale(step)]
+    }
+}
+
+impl<T: Coordinate> TryFrom<&str> for Line<T>
+where
+    <T as std::str::FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(line: &str) -> anyhow::Result<Self> {
+        let (start, end) = line
+            .split_once("->")
+            .ok_or_else(|| anyhow::anyhow!("Could not get points"))?;
+        let (start, end) = (start.trim(), end.trim());
+
+        Ok(Line::new(start.try_into()?, end.try_into()?))
+    }
+}
+
+impl<T: SignedCoordinate> IntoIterator for Line<T> {
+    type Item = Point<T>;
+
+    type IntoIter = LineIterator<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Self::IntoIter::new(self)
+    }
+}
+
+/// Walks the points of a line of any slope using Bresenham's algorithm, which reduces to
+/// exactly the covered points for horizontal, vertical and 45 degree lines
+pub struct LineIterator<T> {
+    current_position: Point<T>,
+    end: Point<T>,
+    delta: Point<T>,
+    step: Point<T>,
+    error: T,
+    finished: bool,
+}
+
+impl<T: SignedCoordinate> LineIterator<T> {
+    fn new(line: Line<T>) -> Self {
+        let vector = line.end - line.start;
+        let delta = Point::new(vector.x.abs(), T::ZERO - vector.y.abs());
+        Self {
+            step: vector.direction(),
+            error: delta.x + delta.y,
+            current_position: line.start,
+            end: line.end,
+            delta,
+            finished: false,
+        }
+    }
+}
+
+impl<T: SignedCoordinate> Iterator for LineIterator<T> {
+    type Item = Point<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let position = self.current_position;
+        if position == self.end {
+            self.finished = true;
+            return Some(position);
+        }
+
+        let doubled_error = self.error + self.error;
+        if doubled_error >= self.delta.y {
+            self.error = self.error + self.delta.y;
+            self.current_position.x = self.current_position.x + self.step.x;
+        }
+        if doubled_error <= self.delta.x {
+            self.error = self.error + self.delta.x;
+            self.current_position.y = self.current_position.y + self.step.y;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn iterator_produces_correct_values() {
+        let line: Line<i32> = "8,0 -> 0,8".try_into().unwrap();
+        let points = line.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(8, 0),
+                Point::new(7, 1),
+                Point::new(6, 2),
+                Point::new(5, 3),
+                Point::new(4, 4),
+                Point::new(3, 5),
+                Point::new(2, 6),
+                Point::new(1, 7),
+                Point::new(0, 8)
+            ]
+        )
+    }
+
+    #[test]
+    fn iterator_produces_correct_values_rev() {
+        let line: Line<i32> = "0,8 -> 8,0".try_into().unwrap();
+        let points = line.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            [
+                Point::new(8, 0),
+                Point::new(7, 1),
+                Point::new(6, 2),
+                Point::new(5, 3),
+                Point::new(4, 4),
+                Point::new(3, 5),
+                Point::new(2, 6),
+                Point::new(1, 7),
+                Point::new(0, 8)
+            ]
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>()
+        )
+    }
+
+    #[test]
+    fn iterator_handles_arbitrary_slopes() {
+        let line: Line<i64> = "0,0 -> 3,1".try_into().unwrap();
+        let points = line.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 1),
+                Point::new(3, 1)
+            ]
+        );
+
+        let line: Line<i64> = "3,7 -> 1,0".try_into().unwrap();
+        let points = line.into_iter().collect::<Vec<_>>();
+        assert_eq!(points.len(), 8);
+        assert_eq!(points.first(), Some(&Point::new(3, 7)));
+        assert_eq!(points.last(), Some(&Point::new(1, 0)));
+    }
+
+    #[test]
+    fn diagonals_crossing_between_points_do_not_overlap() {
+        let line: Line<i32> = "0,0 -> 3,3".try_into().unwrap();
+        let other: Line<i32> = "0,1 -> 1,0".try_into().unwrap();
+        assert!(line.intersections(&other).is_empty());
+    }
+
+    #[test]
+    fn collinear_lines_share_their_overlap() {
+        let line: Line<i32> = "0,0 -> 4,4".try_into().unwrap();
+        let other: Line<i32> = "6,6 -> 2,2".try_into().unwrap();
+        assert_eq!(
+            line.intersections(&other),
+            vec![Point::new(2, 2), Point::new(3, 3), Point::new(4, 4)]
+        );
+    }
+
+    #[test]
+    fn point_lines_intersect_in_either_order() {
+        let point: Line<i32> = "2,2 -> 2,2".try_into().unwrap();
+        let same: Line<i32> = "2,2 -> 2,2".try_into().unwrap();
+        let elsewhere: Line<i32> = "3,2 -> 3,2".try_into().unwrap();
+        let through: Line<i32> = "0,0 -> 4,4".try_into().unwrap();
+        let past: Line<i32> = "0,1 -> 4,5".try_into().unwrap();
+
+        assert_eq!(point.intersections(&same), vec![Point::new(2, 2)]);
+        assert!(point.intersections(&elsewhere).is_empty());
+        assert!(elsewhere.intersections(&point).is_empty());
+        assert_eq!(point.intersections(&through), vec![Point::new(2, 2)]);
+        assert_eq!(through.intersections(&point), vec![Point::new(2, 2)]);
+        assert!(point.intersections(&past).is_empty());
+        assert!(past.intersections(&point).is_empty());
+    }
+
+    #[test]
+    fn bounding_box_and_distances() {
+        let line: Line<usize> = "7,2 -> 3,9".try_into().unwrap();
+        let bounds = line.bounding_box();
+        assert_eq!(bounds.min, Point::new(3, 2));
+        assert_eq!((bounds.width(), bounds.height()), (5, 8));
+        assert!(bounds.contains(&Point::new(5, 5)));
+        assert!(!bounds.contains(&Point::new(2, 5)));
+        assert_eq!(line.start.manhattan_distance(&line.end), 11);
+        assert_eq!(line.steps(), 7);
+    }
+}
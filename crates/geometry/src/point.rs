@@ -0,0 +1,111 @@
+use crate::{Coordinate, SignedCoordinate};
+use std::convert::TryFrom;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Coordinate> Point<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> T {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    pub fn chebyshev_distance(&self, other: &Self) -> T {
+        std::cmp::max(self.x.abs_diff(other.x), self.y.abs_diff(other.y))
+    }
+
+    pub fn cross(&self, other: &Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn scale(&self, factor: T) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+}
+
+impl<T: SignedCoordinate> Point<T> {
+    /// The vector clamped to a single step in each axis
+    pub fn direction(&self) -> Self {
+        Self {
+            x: self.x.signum(),
+            y: self.y.signum(),
+        }
+    }
+}
+
+impl<T> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T> From<Point<T>> for (T, T) {
+    fn from(point: Point<T>) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl<T: Coordinate> TryFrom<&str> for Point<T>
+where
+    <T as std::str::FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    type Error = anyhow::Error;
+
+    fn try_from(line: &str) -> anyhow::Result<Self> {
+        let (x, y) = line
+            .split_once(',')
+            .ok_or_else(|| anyhow::anyhow!("Could not get coords"))?;
+        let (x, y) = (x.trim().parse::<T>()?, y.trim().parse::<T>()?);
+
+        Ok(Self { x, y })
+    }
+}
+
+impl<T: Coordinate> Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl<T: Coordinate> Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl<T: Coordinate> AddAssign for Point<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Coordinate> SubAssign for Point<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
@@ -1,13 +1,17 @@
+mod render;
+
 use anyhow::{Context, Result};
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::ops::{Add, Sub};
 use std::path::Path;
 
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
+type Line = geometry::Line<i32>;
+
 fn read_lines<P>(filename: &P) -> Result<Vec<String>>
 where
     P: AsRef<Path>,
@@ -19,132 +23,46 @@ where
         .collect()
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Ord, PartialOrd)]
-pub struct Point {
-    pub x: i32,
-    pub y: i32,
-}
-
-impl Point {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-
-    pub fn direction(&self) -> Self {
-        Self {
-            x: self.x.clamp(-1, 1),
-            y: self.y.clamp(-1, 1),
-        }
-    }
-}
-
-impl TryFrom<&str> for Point {
-    type Error = anyhow::Error;
-
-    fn try_from(line: &str) -> Result<Self> {
-        let (x, y) = line
-            .split_once(",")
-            .ok_or_else(|| anyhow::anyhow!("Could not get coords"))?;
-        let (x, y) = (x.trim().parse::<i32>()?, y.trim().parse::<i32>()?);
+/// Below this many lines it's cheaper to compare every pair than to sort for a sweep
+const SWEEP_THRESHOLD: usize = 64;
 
-        Ok(Self { x, y })
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapStrategy {
+    /// Walk every point of every line into a grid
+    Rasterize,
+    /// Intersect lines pairwise, proportional to the number of lines rather than their length
+    Analytic,
 }
 
-impl Sub for Point {
-    type Output = Self;
-
-    fn sub(self, other: Self) -> Self::Output {
-        Self {
-            x: self.x - other.x,
-            y: self.y - other.y,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKinds {
+    /// Only horizontal and vertical lines
+    AxisAligned,
+    /// Horizontal, vertical and 45 degree lines
+    Diagonal,
+    /// Lines of any slope, rasterized with Bresenham
+    AnySlope,
 }
 
-impl Add for Point {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self::Output {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+impl LineKinds {
+    pub fn admits(&self, line: &Line) -> bool {
+        match self {
+            LineKinds::AxisAligned => line.is_axis_aligned(),
+            LineKinds::Diagonal => line.is_axis_aligned() || line.is_diagonal(),
+            LineKinds::AnySlope => true,
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct Line {
-    pub start: Point,
-    pub end: Point,
-}
-
-impl Line {
-    fn new(start: Point, end: Point) -> Self {
-        Self { start, end }
-    }
-}
-
-impl TryFrom<&str> for Line {
-    type Error = anyhow::Error;
-
-    fn try_from(line: &str) -> Result<Self> {
-        let (start, end) = line
-            .split_once("->")
-            .ok_or_else(|| anyhow::anyhow!("Could not get points"))?;
-        let (start, end) = (start.trim(), end.trim());
-
-        Ok(Line::new(start.try_into()?, end.try_into()?))
-    }
-}
-
-impl IntoIterator for Line {
-    type Item = Point;
-
-    type IntoIter = LineIterator;
-
-    fn into_iter(self) -> Self::IntoIter {
-        Self::IntoIter::new(self)
-    }
-}
-
-pub struct LineIterator {
-    line: Line,
-    current_position: Point,
-}
-
-impl LineIterator {
-    fn new(line: Line) -> LineIterator {
-        LineIterator {
-            current_position: line.start.clone(),
-            line,
-        }
-    }
-}
-
-impl Iterator for LineIterator {
-    type Item = Point;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let vector = self.line.end.clone() - self.line.start.clone();
-        let direction = vector.direction();
-
-        if self.current_position == (self.line.end.clone() + direction.clone()) {
-            return None;
-        }
-
-        let position = self.current_position.clone();
-        self.current_position = self.current_position.clone() + direction;
-        Some(position)
-    }
-}
-
-fn part_one(lines: &[String]) -> Result<i32> {
-    let lines: Result<Vec<Line>> = lines
+fn parse_lines(lines: &[String]) -> Result<Vec<Line>> {
+    lines
         .iter()
         .map(|line| Line::try_from(line.as_str()))
-        .collect();
-    let lines = lines?;
+        .collect()
+}
+
+/// Number of lines covering each point, indexed by `[y][x]`
+fn vent_grid(lines: &[Line]) -> Result<Vec<Vec<i32>>> {
     let max_x = lines
         .iter()
         .map(|line| std::cmp::max(line.start.x, line.end.x))
@@ -157,60 +75,94 @@ fn part_one(lines: &[String]) -> Result<i32> {
         .max()
         .ok_or_else(|| anyhow::anyhow!("Couldn't determine biggest y value"))?;
 
+    if let Some(line) = lines.iter().find(|line| {
+        let bounds = line.bounding_box();
+        std::cmp::min(bounds.min.x, bounds.min.y) < 0
+    }) {
+        anyhow::bail!("Can't rasterize {:?} with negative coordinates", line);
+    }
+
     let mut grid: Vec<Vec<i32>> = vec![vec![0; (max_x + 1) as usize]; (max_y + 1) as usize];
-    let lines = lines
-        .iter()
-        .filter(|line| line.start.x == line.end.x || line.start.y == line.end.y)
-        .cloned()
-        .collect::<Vec<_>>();
 
     for line in lines {
-        for point in line {
+        for point in *line {
             grid[point.y as usize][point.x as usize] += 1;
         }
     }
 
-    Ok(grid
-        .iter()
-        .flatten()
-        .filter(|val| **val >= 2)
-        .cloned()
-        .count() as i32)
+    Ok(grid)
 }
 
-fn part_two(lines: &[String]) -> Result<i32> {
-    let lines: Result<Vec<Line>> = lines
-        .iter()
-        .map(|line| Line::try_from(line.as_str()))
-        .collect();
-    let lines = lines?;
-    let max_x = lines
-        .iter()
-        .map(|line| std::cmp::max(line.start.x, line.end.x))
-        .max()
-        .ok_or_else(|| anyhow::anyhow!("Couldn't determine biggest x value"))?;
+fn rasterize_overlaps(lines: &[Line]) -> Result<usize> {
+    let grid = vent_grid(lines)?;
+    Ok(grid.iter().flatten().filter(|val| **val >= 2).count())
+}
 
-    let max_y = lines
-        .iter()
-        .map(|line| std::cmp::max(line.start.y, line.end.y))
-        .max()
-        .ok_or_else(|| anyhow::anyhow!("Couldn't determine biggest y value"))?;
+fn analytic_overlaps(lines: &[Line]) -> Result<usize> {
+    if let Some(line) = lines.iter().find(|line| !LineKinds::Diagonal.admits(line)) {
+        anyhow::bail!("Can't intersect {:?} analytically, only horizontal, vertical and 45 degree lines are supported", line);
+    }
 
-    let mut grid: Vec<Vec<i32>> = vec![vec![0; (max_x + 1) as usize]; (max_y + 1) as usize];
+    let mut overlaps = HashSet::new();
 
-    for line in lines {
-        for point in line {
-            grid[point.y as usize][point.x as usize] += 1;
+    if lines.len() < SWEEP_THRESHOLD {
+        for (index, line) in lines.iter().enumerate() {
+            for other in &lines[index + 1..] {
+                overlaps.extend(line.intersections(other));
+            }
         }
+        return Ok(overlaps.len());
     }
 
-    // dbg!(&grid);
-    Ok(grid
-        .iter()
-        .flatten()
-        .filter(|val| **val >= 2)
-        .cloned()
-        .count() as i32)
+    // Sweep from left to right, only comparing against lines still spanning the sweep position
+    let mut ordered = lines.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|line| line.bounding_box().min.x);
+    let mut active: Vec<&Line> = Vec::new();
+
+    for line in ordered {
+        let bounds = line.bounding_box();
+        active.retain(|other| other.bounding_box().max.x >= bounds.min.x);
+
+        for other in &active {
+            if bounds.intersects(&other.bounding_box()) {
+                overlaps.extend(line.intersections(other));
+            }
+        }
+        active.push(line);
+    }
+
+    Ok(overlaps.len())
+}
+
+fn count_overlaps(lines: &[Line], strategy: OverlapStrategy) -> Result<usize> {
+    match strategy {
+        OverlapStrategy::Rasterize => rasterize_overlaps(lines),
+        OverlapStrategy::Analytic => analytic_overlaps(lines),
+    }
+}
+
+fn parse_lines_of_kind(lines: &[String], kinds: LineKinds) -> Result<Vec<Line>> {
+    Ok(parse_lines(lines)?
+        .into_iter()
+        .filter(|line| kinds.admits(line))
+        .collect())
+}
+
+fn count_line_overlaps(
+    lines: &[String],
+    kinds: LineKinds,
+    strategy: OverlapStrategy,
+) -> Result<usize> {
+    let lines = parse_lines_of_kind(lines, kinds)?;
+    count_overlaps(&lines, strategy)
+}
+
+fn part_one(lines: &[String], strategy: OverlapStrategy) -> Result<usize> {
+    count_line_overlaps(lines, LineKinds::AxisAligned, strategy)
+}
+
+fn part_two(lines: &[String], strategy: OverlapStrategy) -> Result<usize> {
+    count_line_overlaps(lines, LineKinds::Diagonal, strategy)
 }
 
 fn main() -> Result<()> {
@@ -220,10 +172,29 @@ fn main() -> Result<()> {
         .join(INPUT_FILE_PATH);
 
     let input = read_lines(&input_path)?;
-    println!("{:?}", part_one(&input)?);
-    println!("{:?}", part_two(&input)?);
-    assert_eq!(part_one(&input)?, 5147);
-    assert_eq!(part_two(&input)?, 16925);
+    println!("{:?}", part_one(&input, OverlapStrategy::Analytic)?);
+    println!("{:?}", part_two(&input, OverlapStrategy::Analytic)?);
+    assert_eq!(part_one(&input, OverlapStrategy::Analytic)?, 5147);
+    assert_eq!(part_two(&input, OverlapStrategy::Analytic)?, 16925);
+
+    let args = std::env::args().collect::<Vec<_>>();
+    let kinds = if args.iter().any(|arg| arg == "--any-slope") {
+        println!(
+            "{:?}",
+            count_line_overlaps(&input, LineKinds::AnySlope, OverlapStrategy::Rasterize)?
+        );
+        LineKinds::AnySlope
+    } else {
+        LineKinds::Diagonal
+    };
+
+    if let Some(position) = args.iter().position(|arg| arg == "--render") {
+        let directory = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--render needs an output directory"))?;
+        let grid = vent_grid(&parse_lines_of_kind(&input, kinds)?)?;
+        render::write_all(&grid, Path::new(directory))?;
+    }
 
     Ok(())
 }
@@ -232,47 +203,107 @@ fn main() -> Result<()> {
 mod test {
     use super::*;
 
+    const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    fn example() -> Vec<String> {
+        EXAMPLE.lines().map(String::from).collect()
+    }
+
     #[test]
-    fn iterator_produces_correct_values() {
-        let line: Line = "8,0 -> 0,8".try_into().unwrap();
-        let points = line.into_iter().collect::<Vec<Point>>();
+    fn strategies_agree_on_example() {
+        let input = example();
+        for strategy in [OverlapStrategy::Rasterize, OverlapStrategy::Analytic] {
+            assert_eq!(part_one(&input, strategy).unwrap(), 5);
+            assert_eq!(part_two(&input, strategy).unwrap(), 12);
+        }
+    }
+
+    #[test]
+    fn strategies_agree_on_input() {
+        let input =
+            read_lines(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/input.txt")).unwrap();
+        assert!(input.len() >= SWEEP_THRESHOLD);
+        assert_eq!(
+            part_one(&input, OverlapStrategy::Rasterize).unwrap(),
+            part_one(&input, OverlapStrategy::Analytic).unwrap()
+        );
         assert_eq!(
-            points,
-            vec![
-                Point::new(8, 0),
-                Point::new(7, 1),
-                Point::new(6, 2),
-                Point::new(5, 3),
-                Point::new(4, 4),
-                Point::new(3, 5),
-                Point::new(2, 6),
-                Point::new(1, 7),
-                Point::new(0, 8)
-            ]
-        )
+            part_two(&input, OverlapStrategy::Rasterize).unwrap(),
+            part_two(&input, OverlapStrategy::Analytic).unwrap()
+        );
     }
 
     #[test]
-    fn iterator_produces_correct_values_rev() {
-        let line: Line = "0,8 -> 8,0".try_into().unwrap();
-        let points = line.into_iter().collect::<Vec<Point>>();
+    fn strategies_agree_on_point_lines() {
+        let input: Vec<String> = [
+            "1,1 -> 1,1",
+            "0,0 -> 2,2",
+            "1,1 -> 1,1",
+            "5,5 -> 5,5",
+            "5,4 -> 5,6",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        for strategy in [OverlapStrategy::Rasterize, OverlapStrategy::Analytic] {
+            assert_eq!(part_one(&input, strategy).unwrap(), 2);
+            assert_eq!(part_two(&input, strategy).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn analytic_rejects_arbitrary_slopes() {
+        let input = vec!["0,0 -> 4,2".to_string(), "0,1 -> 4,1".to_string()];
+        assert!(
+            count_line_overlaps(&input, LineKinds::AnySlope, OverlapStrategy::Analytic).is_err()
+        );
+        assert_eq!(
+            count_line_overlaps(&input, LineKinds::AnySlope, OverlapStrategy::Rasterize).unwrap(),
+            2
+        );
         assert_eq!(
-            points,
-            vec![
-                Point::new(8, 0),
-                Point::new(7, 1),
-                Point::new(6, 2),
-                Point::new(5, 3),
-                Point::new(4, 4),
-                Point::new(3, 5),
-                Point::new(2, 6),
-                Point::new(1, 7),
-                Point::new(0, 8)
-            ]
-            .iter()
-            .rev()
-            .cloned()
-            .collect::<Vec<Point>>()
-        )
+            count_line_overlaps(&input, LineKinds::Diagonal, OverlapStrategy::Analytic).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn renders_example_like_the_puzzle() {
+        let lines = parse_lines_of_kind(&example(), LineKinds::Diagonal).unwrap();
+        let grid = vent_grid(&lines).unwrap();
+        assert_eq!(
+            render::ascii(&grid),
+            "1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111...."
+        );
+    }
+
+    #[test]
+    fn downsampling_keeps_overlaps() {
+        let lines = parse_lines_of_kind(&example(), LineKinds::Diagonal).unwrap();
+        let grid = render::downsample(&vent_grid(&lines).unwrap(), 4);
+        assert_eq!(render::ascii(&grid), "212.\n1331\n1.1.\n21..");
+
+        let mut pgm = Vec::new();
+        render::write_pgm(&grid, &mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n4 4\n255\n"));
+        assert_eq!(pgm.len(), b"P5\n4 4\n255\n".len() + 16);
     }
 }
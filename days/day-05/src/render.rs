@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Text maps wider or taller than this are downsampled to stay readable in a terminal
+pub const ASCII_MAX_SIZE: usize = 200;
+/// Images wider or taller than this are downsampled
+pub const IMAGE_MAX_SIZE: usize = 1024;
+
+/// Shrinks the grid so neither side exceeds `max_size`, keeping the highest count of each block
+/// so that overlaps don't disappear
+pub fn downsample(grid: &[Vec<i32>], max_size: usize) -> Vec<Vec<i32>> {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    let factor = std::cmp::max(
+        1,
+        std::cmp::max(width.div_ceil(max_size), height.div_ceil(max_size)),
+    );
+    if factor == 1 {
+        return grid.to_vec();
+    }
+
+    grid.chunks(factor)
+        .map(|rows| {
+            (0..width)
+                .step_by(factor)
+                .map(|x| {
+                    rows.iter()
+                        .flat_map(|row| &row[x..std::cmp::min(x + factor, width)])
+                        .copied()
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The puzzle's own rendering, `.` for no vents and the count otherwise, `#` above 9
+pub fn ascii(grid: &[Vec<i32>]) -> String {
+    grid.iter()
+        .map(|row| {
+            row.iter()
+                .map(|count| match count {
+                    0 => '.',
+                    1..=9 => char::from_digit(*count as u32, 10).unwrap_or('#'),
+                    _ => '#',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Grayscale intensities scaled so the most overlapped point is white
+fn intensities(grid: &[Vec<i32>]) -> Vec<u8> {
+    let max_count = grid.iter().flatten().copied().max().unwrap_or(0).max(1);
+    grid.iter()
+        .flatten()
+        .map(|count| (*count * 255 / max_count) as u8)
+        .collect()
+}
+
+pub fn write_pgm<W: Write>(grid: &[Vec<i32>], mut writer: W) -> Result<()> {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    write!(writer, "P5\n{} {}\n255\n", width, height)?;
+    writer.write_all(&intensities(grid))?;
+    Ok(())
+}
+
+#[cfg(feature = "png")]
+pub fn write_png<W: Write>(grid: &[Vec<i32>], writer: W) -> Result<()> {
+    let height = grid.len();
+    let width = grid.first().map_or(0, Vec::len);
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()?
+        .write_image_data(&intensities(grid))?;
+    Ok(())
+}
+
+/// Writes `vents.txt`, `vents.pgm` and, with the `png` feature, `vents.png` into `directory`
+pub fn write_all(grid: &[Vec<i32>], directory: &Path) -> Result<()> {
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Could not create {}", directory.display()))?;
+
+    std::fs::write(
+        directory.join("vents.txt"),
+        ascii(&downsample(grid, ASCII_MAX_SIZE)),
+    )?;
+
+    let image = downsample(grid, IMAGE_MAX_SIZE);
+    write_pgm(
+        &image,
+        BufWriter::new(File::create(directory.join("vents.pgm"))?),
+    )?;
+
+    #[cfg(feature = "png")]
+    write_png(
+        &image,
+        BufWriter::new(File::create(directory.join("vents.png"))?),
+    )?;
+
+    Ok(())
+}
@@ -1,15 +1,123 @@
 use anyhow::{Context, Result};
 use itertools::Itertools;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::num::NonZeroU64;
 use std::path::Path;
 
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-const RESET_VALUE: u64 = 6;
-const SPAWN_VALUE: u64 = 8;
+/// Timer values `0..TIMER_STATES` tracked by the matrix simulation
+const TIMER_STATES: usize = 9;
+
+/// Largest timer a species may use, every census keeps a count for each timer up to it
+const MAX_TIMER: u64 = 1024;
+
+/// How a single kind of fish counts down and reproduces
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Species {
+    name: String,
+    /// Timer a fish goes back to after spawning
+    reset_timer: u64,
+    /// Timer a newborn fish starts with
+    spawn_timer: u64,
+    /// Extra days a newborn waits before its timer starts counting down
+    #[serde(default)]
+    maturation_delay: u64,
+    /// Fraction of every timer group that dies each day
+    #[serde(default)]
+    mortality: f64,
+    /// Starting timers, the puzzle input is used when left out
+    #[serde(default)]
+    initial: Option<Vec<u64>>,
+}
+
+impl Species {
+    fn lanternfish() -> Self {
+        Self {
+            name: "lanternfish".to_string(),
+            reset_timer: 6,
+            spawn_timer: 8,
+            maturation_delay: 0,
+            mortality: 0.0,
+            initial: None,
+        }
+    }
+
+    /// Timer value of a fish on the day it's born
+    fn newborn_timer(&self) -> u64 {
+        self.spawn_timer.saturating_add(self.maturation_delay)
+    }
+
+    /// Checks that the timers and mortality stay in range
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.mortality) {
+            anyhow::bail!("Mortality of {} must be between 0 and 1", self.name);
+        }
+        let newborn_timer = self
+            .spawn_timer
+            .checked_add(self.maturation_delay)
+            .filter(|timer| *timer <= MAX_TIMER);
+        if newborn_timer.is_none()
+            || self.reset_timer > MAX_TIMER
+            || self
+                .initial
+                .iter()
+                .flatten()
+                .any(|timer| *timer > MAX_TIMER)
+        {
+            anyhow::bail!("Timers of {} can't go above {}", self.name, MAX_TIMER);
+        }
+        Ok(())
+    }
+}
+
+/// Every species living in one population, loaded from TOML like
+///
+/// ```toml
+/// population_cap = 1000000
+///
+/// [[species]]
+/// name = "lanternfish"
+/// reset_timer = 6
+/// spawn_timer = 8
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Lifecycle {
+    species: Vec<Species>,
+    /// Births are dropped once the whole population would grow past this
+    #[serde(default)]
+    population_cap: Option<u64>,
+}
+
+impl Lifecycle {
+    fn lanternfish() -> Self {
+        Self {
+            species: vec![Species::lanternfish()],
+            population_cap: None,
+        }
+    }
+
+    fn from_toml_str(contents: &str) -> Result<Self> {
+        let lifecycle: Self = toml::from_str(contents).context("Could not parse lifecycle")?;
+        if lifecycle.species.is_empty() {
+            anyhow::bail!("Lifecycle needs at least one species");
+        }
+        for species in &lifecycle.species {
+            species.validate()?;
+        }
+        Ok(lifecycle)
+    }
+
+    fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Could not read {}", path.as_ref().display()))?;
+        Self::from_toml_str(&contents)
+    }
+}
 
 fn read_lines<P>(filename: &P) -> Result<Vec<String>>
 where
@@ -22,49 +130,381 @@ where
         .collect()
 }
 
-fn hash_simulate(inputs: &[u64], max_steps: u64) -> Result<u64> {
-    let final_map = (0..max_steps).fold(inputs.iter().cloned().counts(), |acc, _| {
-        let mut new_hash: HashMap<u64, usize> = HashMap::new();
+/// Fish counts on a single day, indexed by species and then timer
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Census(Vec<Vec<u64>>);
+
+impl Census {
+    fn from_population(
+        lifecycle: &Lifecycle,
+        population: &HashMap<(usize, u64), u64>,
+    ) -> Result<Self> {
+        let mut timers = lifecycle
+            .species
+            .iter()
+            .map(|species| {
+                vec![0; std::cmp::max(species.reset_timer, species.newborn_timer()) as usize + 1]
+            })
+            .collect::<Vec<_>>();
+        for ((species, timer), count) in population {
+            let counts = &mut timers[*species];
+            if counts.len() <= *timer as usize {
+                counts.resize(*timer as usize + 1, 0);
+            }
+            add_fish(&mut counts[*timer as usize], *count)?;
+        }
+        Ok(Self(timers))
+    }
+
+    /// Totals are summed as u128, which can't overflow for fewer than 2^64 timer counts
+    fn species_totals(&self) -> Vec<u128> {
+        self.0
+            .iter()
+            .map(|timers| timers.iter().map(|count| u128::from(*count)).sum())
+            .collect()
+    }
+
+    fn total(&self) -> u128 {
+        self.species_totals().iter().sum()
+    }
+}
+
+/// Adds `more` fish to `count`, failing instead of wrapping around
+fn add_fish(count: &mut u64, more: u64) -> Result<()> {
+    *count = count
+        .checked_add(more)
+        .ok_or_else(|| anyhow::anyhow!("Fish count overflowed a u64"))?;
+    Ok(())
+}
+
+/// Census of every day, starting with the initial population on day 0
+fn hash_simulate(
+    lifecycle: &Lifecycle,
+    inputs: &[Vec<u64>],
+    max_steps: u64,
+) -> Result<Vec<Census>> {
+    if inputs.len() != lifecycle.species.len() {
+        anyhow::bail!(
+            "Got {} initial populations for {} species",
+            inputs.len(),
+            lifecycle.species.len()
+        );
+    }
 
-        for (key, value) in acc {
+    let mut population: HashMap<(usize, u64), u64> = HashMap::new();
+    for (species, timers) in inputs.iter().enumerate() {
+        for (timer, count) in timers.iter().cloned().counts() {
+            add_fish(
+                population.entry((species, timer)).or_default(),
+                count as u64,
+            )?;
+        }
+    }
+
+    let mut history = vec![Census::from_population(lifecycle, &population)?];
+    for _ in 0..max_steps {
+        let mut new_hash: HashMap<(usize, u64), u64> = HashMap::new();
+        let mut births = vec![0; lifecycle.species.len()];
+
+        for ((species, key), value) in population {
+            let config = &lifecycle.species[species];
+            let survivors = value - (value as f64 * config.mortality).floor() as u64;
             if key == 0 {
-                *new_hash.entry(RESET_VALUE).or_default() += value;
-                *new_hash.entry(SPAWN_VALUE).or_default() += value;
+                add_fish(
+                    new_hash.entry((species, config.reset_timer)).or_default(),
+                    survivors,
+                )?;
+                add_fish(&mut births[species], survivors)?;
             } else {
                 let next_key = key - 1;
-                *new_hash.entry(next_key).or_default() += value;
+                add_fish(new_hash.entry((species, next_key)).or_default(), survivors)?;
             };
         }
 
-        new_hash
-    });
+        let mut room = lifecycle.population_cap.map(|cap| {
+            let total = new_hash
+                .values()
+                .map(|count| u128::from(*count))
+                .sum::<u128>();
+            u64::try_from(total).map_or(0, |total| cap.saturating_sub(total))
+        });
+        for (species, count) in births.into_iter().enumerate() {
+            let count = match room.as_mut() {
+                Some(room) => {
+                    let admitted = std::cmp::min(count, *room);
+                    *room -= admitted;
+                    admitted
+                }
+                None => count,
+            };
+            if count > 0 {
+                let newborn_timer = lifecycle.species[species].newborn_timer();
+                add_fish(new_hash.entry((species, newborn_timer)).or_default(), count)?;
+            }
+        }
+
+        population = new_hash;
+        history.push(Census::from_population(lifecycle, &population)?);
+    }
 
-    Ok(final_map.values().sum::<usize>() as u64)
+    Ok(history)
 }
 
-fn part_one(lines: &[String]) -> Result<u64> {
-    let line = lines
-        .first()
-        .ok_or_else(|| anyhow::anyhow!("Could not get input line"))?;
-    let inputs: Result<Vec<u64>> = line
-        .split(',')
-        .map(|val| val.parse::<u64>().context("Could not parse u64"))
-        .collect();
-    let inputs = inputs?;
+/// Fish counts indexed by timer value
+type State = [u128; TIMER_STATES];
+
+/// `matrix[to][from]` is how many fish with timer `to` a single fish with timer `from` becomes
+/// after one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TransitionMatrix([State; TIMER_STATES]);
+
+/// Adds `a * b` to `total`, reducing by `modulus` when given and failing on overflow otherwise
+fn multiply_add(total: u128, a: u128, b: u128, modulus: Option<NonZeroU64>) -> Result<u128> {
+    match modulus {
+        // Both factors are already reduced below a u64, so the product always fits
+        Some(modulus) => {
+            let modulus = u128::from(modulus.get());
+            Ok((total + a * b % modulus) % modulus)
+        }
+        None => a
+            .checked_mul(b)
+            .and_then(|product| total.checked_add(product))
+            .ok_or_else(|| anyhow::anyhow!("Fish count overflowed a u128, try a modulus")),
+    }
+}
+
+impl TransitionMatrix {
+    fn identity() -> Self {
+        let mut matrix = [[0; TIMER_STATES]; TIMER_STATES];
+        for (index, row) in matrix.iter_mut().enumerate() {
+            row[index] = 1;
+        }
+        Self(matrix)
+    }
+
+    /// Only immortal species whose timers fit in `TIMER_STATES` can be expressed as a matrix
+    fn for_species(species: &Species) -> Result<Self> {
+        let (reset, newborn) = (
+            species.reset_timer as usize,
+            species.newborn_timer() as usize,
+        );
+        if std::cmp::max(reset, newborn) >= TIMER_STATES {
+            anyhow::bail!("Timers of {} don't fit in the matrix", species.name);
+        }
+        if species.mortality != 0.0 {
+            anyhow::bail!("Mortality of {} can't be simulated exactly", species.name);
+        }
+
+        let mut matrix = [[0; TIMER_STATES]; TIMER_STATES];
+        for timer in 1..TIMER_STATES {
+            matrix[timer - 1][timer] = 1;
+        }
+        matrix[reset][0] += 1;
+        matrix[newborn][0] += 1;
+        Ok(Self(matrix))
+    }
+
+    fn multiply(&self, other: &Self, modulus: Option<NonZeroU64>) -> Result<Self> {
+        let mut result = [[0; TIMER_STATES]; TIMER_STATES];
+        for (row, result_row) in result.iter_mut().enumerate() {
+            for (column, cell) in result_row.iter_mut().enumerate() {
+                for (inner, other_row) in other.0.iter().enumerate() {
+                    *cell = multiply_add(*cell, self.0[row][inner], other_row[column], modulus)?;
+                }
+            }
+        }
+        Ok(Self(result))
+    }
+
+    /// Raises the matrix to `exponent` by repeated squaring, so a day count of 10^12 takes
+    /// about 40 multiplications
+    fn pow(&self, mut exponent: u64, modulus: Option<NonZeroU64>) -> Result<Self> {
+        let mut result = Self::identity();
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base, modulus)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base, modulus)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn apply(&self, state: &State, modulus: Option<NonZeroU64>) -> Result<State> {
+        let mut result = [0; TIMER_STATES];
+        for (row, cell) in result.iter_mut().enumerate() {
+            for (column, count) in state.iter().enumerate() {
+                *cell = multiply_add(*cell, self.0[row][column], *count, modulus)?;
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn initial_state(inputs: &[u64], modulus: Option<NonZeroU64>) -> Result<State> {
+    let mut state = [0; TIMER_STATES];
+    for timer in inputs {
+        let count = state
+            .get_mut(*timer as usize)
+            .ok_or_else(|| anyhow::anyhow!("Timer {} doesn't fit in the matrix", timer))?;
+        *count = multiply_add(*count, 1, 1, modulus)?;
+    }
+    Ok(state)
+}
+
+/// Total fish after `days`, optionally modulo `modulus`, in O(log days) matrix multiplications
+fn matrix_simulate(
+    species: &Species,
+    inputs: &[u64],
+    days: u64,
+    modulus: Option<NonZeroU64>,
+) -> Result<u128> {
+    let state = initial_state(inputs, modulus)?;
+    let state = TransitionMatrix::for_species(species)?
+        .pow(days, modulus)?
+        .apply(&state, modulus)?;
+
+    state
+        .iter()
+        .try_fold(0, |total, count| multiply_add(total, *count, 1, modulus))
+}
+
+/// First day on which the population is larger than `threshold`, found by stepping the exact
+/// counts forward, `None` when there are no fish to grow
+fn first_day_exceeding(species: &Species, inputs: &[u64], threshold: u128) -> Result<Option<u64>> {
+    let transition = TransitionMatrix::for_species(species)?;
+    let mut state = initial_state(inputs, None)?;
+    if state.iter().all(|count| *count == 0) {
+        return Ok(None);
+    }
+
+    let total = |state: &State| {
+        state
+            .iter()
+            .try_fold(0_u128, |total, count| total.checked_add(*count))
+            .ok_or_else(|| anyhow::anyhow!("Fish count overflowed a u128"))
+    };
+    let mut day = 0;
+    while total(&state)? <= threshold {
+        state = transition.apply(&state, None)?;
+        day += 1;
+    }
+    Ok(Some(day))
+}
+
+/// Factor the population eventually grows by every day, the dominant eigenvalue of the
+/// transition, found by power iteration
+fn growth_rate(species: &Species) -> f64 {
+    let size = std::cmp::max(species.reset_timer, species.newborn_timer()) as usize + 1;
+    let survival = 1.0 - species.mortality;
+    let step = |counts: &[f64]| {
+        let mut next = vec![0.0; size];
+        for timer in 1..size {
+            next[timer - 1] += counts[timer] * survival;
+        }
+        next[species.reset_timer as usize] += counts[0] * survival;
+        next[species.newborn_timer() as usize] += counts[0] * survival;
+        next
+    };
+
+    let mut counts = vec![1.0; size];
+    let mut rate = 0.0;
+    for _ in 0..10_000 {
+        let next = step(&counts);
+        let total = next.iter().sum::<f64>();
+        if total == 0.0 {
+            return 0.0;
+        }
+        let next_rate = total / counts.iter().sum::<f64>();
+        counts = next.iter().map(|count| count / total).collect();
+        if (next_rate - rate).abs() < 1e-12 {
+            return next_rate;
+        }
+        rate = next_rate;
+    }
+    rate
+}
+
+/// Writes one row per day and species with the total and the count for every timer value
+fn write_time_series<W: std::io::Write>(
+    lifecycle: &Lifecycle,
+    history: &[Census],
+    writer: W,
+) -> Result<()> {
+    let timers = history
+        .iter()
+        .flat_map(|census| census.0.iter().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+    let mut writer = csv::Writer::from_writer(writer);
 
-    hash_simulate(&inputs, 80)
+    let mut header = vec![
+        "day".to_string(),
+        "species".to_string(),
+        "total".to_string(),
+    ];
+    header.extend((0..timers).map(|timer| format!("timer_{}", timer)));
+    writer.write_record(&header)?;
+
+    for (day, census) in history.iter().enumerate() {
+        for (species, counts) in lifecycle.species.iter().zip(&census.0) {
+            let mut record = vec![
+                day.to_string(),
+                species.name.clone(),
+                counts
+                    .iter()
+                    .map(|count| u128::from(*count))
+                    .sum::<u128>()
+                    .to_string(),
+            ];
+            record.extend((0..timers).map(|timer| counts.get(timer).unwrap_or(&0).to_string()));
+            writer.write_record(&record)?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
 }
 
-fn part_two(lines: &[String]) -> Result<u64> {
+fn parse_timers(lines: &[String]) -> Result<Vec<u64>> {
     let line = lines
         .first()
         .ok_or_else(|| anyhow::anyhow!("Could not get input line"))?;
-    let inputs: Result<Vec<u64>> = line
-        .split(',')
+    line.split(',')
         .map(|val| val.parse::<u64>().context("Could not parse u64"))
-        .collect();
-    let inputs = inputs?;
-    hash_simulate(&inputs, 256)
+        .collect()
+}
+
+fn part_one(lines: &[String]) -> Result<u128> {
+    let inputs = parse_timers(lines)?;
+    let history = hash_simulate(&Lifecycle::lanternfish(), &[inputs], 80)?;
+    Ok(history.last().map_or(0, Census::total))
+}
+
+fn part_two(lines: &[String]) -> Result<u128> {
+    let inputs = parse_timers(lines)?;
+    matrix_simulate(&Species::lanternfish(), &inputs, 256, None)
+}
+
+/// Value following `flag` on the command line, if it was passed
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Result<Option<T>>
+where
+    <T as std::str::FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    match args.iter().position(|arg| arg == flag) {
+        Some(position) => {
+            let value = args
+                .get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", flag))?;
+            Ok(Some(value.parse::<T>().with_context(|| {
+                format!("Could not parse {} value {:?}", flag, value)
+            })?))
+        }
+        None => Ok(None),
+    }
 }
 
 fn main() -> Result<()> {
@@ -77,5 +517,206 @@ fn main() -> Result<()> {
     println!("{:?}", part_one(&input)?);
     println!("{:?}", part_two(&input)?);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    let species_file = flag_value::<String>(&args, "--species")?;
+    let days = flag_value::<u64>(&args, "--days")?;
+    if let (Some(days), None) = (days, &species_file) {
+        let modulus = flag_value::<NonZeroU64>(&args, "--modulo")?;
+        println!(
+            "{:?}",
+            matrix_simulate(
+                &Species::lanternfish(),
+                &parse_timers(&input)?,
+                days,
+                modulus
+            )?
+        );
+    }
+
+    let lifecycle = match &species_file {
+        Some(path) => Lifecycle::from_toml(path)?,
+        None => Lifecycle::lanternfish(),
+    };
+    let timers = parse_timers(&input)?;
+    let inputs = lifecycle
+        .species
+        .iter()
+        .map(|species| species.initial.clone().unwrap_or_else(|| timers.clone()))
+        .collect::<Vec<_>>();
+
+    if species_file.is_some() {
+        let history = hash_simulate(&lifecycle, &inputs, days.unwrap_or(80))?;
+        if let Some(census) = history.last() {
+            for (species, total) in lifecycle.species.iter().zip(census.species_totals()) {
+                println!("{}: {}", species.name, total);
+            }
+        }
+    }
+
+    if let Some(path) = flag_value::<String>(&args, "--csv")? {
+        let history = hash_simulate(&lifecycle, &inputs, days.unwrap_or(256))?;
+        let file = File::create(&path).with_context(|| format!("Could not create {}", path))?;
+        write_time_series(&lifecycle, &history, file)?;
+    }
+
+    if args.iter().any(|arg| arg == "--growth") {
+        for species in &lifecycle.species {
+            println!("{} growth rate: {:.6}", species.name, growth_rate(species));
+        }
+    }
+
+    if let Some(threshold) = flag_value::<u128>(&args, "--exceeds")? {
+        for (species, timers) in lifecycle.species.iter().zip(&inputs) {
+            match first_day_exceeding(species, timers, threshold)? {
+                Some(day) => println!("{} exceeds {} on day {}", species.name, threshold, day),
+                None => println!("{} never exceeds {}", species.name, threshold),
+            }
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: [u64; 5] = [3, 4, 3, 1, 2];
+
+    #[test]
+    fn matrix_matches_hash_simulation() {
+        for days in [0, 1, 18, 80, 256] {
+            let history =
+                hash_simulate(&Lifecycle::lanternfish(), &[EXAMPLE.to_vec()], days).unwrap();
+            assert_eq!(
+                matrix_simulate(&Species::lanternfish(), &EXAMPLE, days, None).unwrap(),
+                history[days as usize].total()
+            );
+        }
+        assert_eq!(
+            matrix_simulate(&Species::lanternfish(), &EXAMPLE, 256, None).unwrap(),
+            26984457539
+        );
+    }
+
+    #[test]
+    fn modulus_agrees_with_exact_counts() {
+        let exact = matrix_simulate(&Species::lanternfish(), &EXAMPLE, 700, None).unwrap();
+        assert_eq!(
+            matrix_simulate(
+                &Species::lanternfish(),
+                &EXAMPLE,
+                700,
+                NonZeroU64::new(1_000_000_007)
+            )
+            .unwrap(),
+            exact % 1_000_000_007
+        );
+        assert!("0".parse::<NonZeroU64>().is_err());
+        assert!(
+            matrix_simulate(&Species::lanternfish(), &EXAMPLE, 1_000_000_000_000, None).is_err()
+        );
+        assert!(matrix_simulate(
+            &Species::lanternfish(),
+            &EXAMPLE,
+            1_000_000_000_000,
+            NonZeroU64::new(u64::MAX)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn species_share_a_capped_population() {
+        let lifecycle = Lifecycle::from_toml_str(
+            r#"
+            population_cap = 100
+
+            [[species]]
+            name = "lanternfish"
+            reset_timer = 6
+            spawn_timer = 8
+
+            [[species]]
+            name = "slowfish"
+            reset_timer = 2
+            spawn_timer = 2
+            maturation_delay = 3
+            mortality = 0.5
+            "#,
+        )
+        .unwrap();
+        let history = hash_simulate(&lifecycle, &[EXAMPLE.to_vec(), vec![0; 4]], 30).unwrap();
+
+        // Half of the four slowfish die before spawning, the other two each have a newborn
+        assert_eq!(history[1].species_totals(), vec![5, 4]);
+        assert!(history.iter().all(|census| census.total() <= 100));
+
+        let slowfish = &lifecycle.species[1];
+        assert_eq!(slowfish.newborn_timer(), 5);
+        assert!(matrix_simulate(slowfish, &[0], 10, None).is_err());
+    }
+
+    #[test]
+    fn hash_simulation_overflow_is_an_error() {
+        let crowded = [vec![0; 1 << 12]];
+        assert!(hash_simulate(&Lifecycle::lanternfish(), &crowded, 400).is_ok());
+        assert!(hash_simulate(&Lifecycle::lanternfish(), &crowded, 700).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_timers() {
+        let species = |timers: &str| {
+            Lifecycle::from_toml_str(&format!("[[species]]\nname = \"fish\"\n{}", timers))
+        };
+        assert!(species("reset_timer = 6\nspawn_timer = 8").is_ok());
+        assert!(species("reset_timer = 18446744073709551615\nspawn_timer = 8").is_err());
+        assert!(species("reset_timer = 1000000000000\nspawn_timer = 8").is_err());
+        assert!(species(
+            "reset_timer = 6\nspawn_timer = 18446744073709551615\nmaturation_delay = 1"
+        )
+        .is_err());
+        assert!(species("reset_timer = 6\nspawn_timer = 8\ninitial = [3, 5000]").is_err());
+        assert!(species("reset_timer = 6\nspawn_timer = 8\nmortality = 1.5").is_err());
+    }
+
+    #[test]
+    fn growth_analysis() {
+        // The lanternfish rate is the real root of x^9 = x^2 + 1
+        let rate = growth_rate(&Species::lanternfish());
+        assert!((rate.powi(9) - rate.powi(2) - 1.0).abs() < 1e-9);
+        assert!((rate - 1.0910).abs() < 1e-4);
+
+        // 26 fish on day 18 and 5934 on day 80
+        let lanternfish = Species::lanternfish();
+        assert_eq!(
+            first_day_exceeding(&lanternfish, &EXAMPLE, 25).unwrap(),
+            Some(18)
+        );
+        assert_eq!(
+            first_day_exceeding(&lanternfish, &EXAMPLE, 5933).unwrap(),
+            Some(80)
+        );
+        assert_eq!(
+            first_day_exceeding(&lanternfish, &EXAMPLE, 5).unwrap(),
+            Some(2)
+        );
+        assert_eq!(first_day_exceeding(&lanternfish, &[], 5).unwrap(), None);
+        assert!(first_day_exceeding(&lanternfish, &EXAMPLE, u128::MAX - 1).is_err());
+    }
+
+    #[test]
+    fn time_series_as_csv() {
+        let lifecycle = Lifecycle::lanternfish();
+        let history = hash_simulate(&lifecycle, &[EXAMPLE.to_vec()], 2).unwrap();
+        let mut output = Vec::new();
+        write_time_series(&lifecycle, &history, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "day,species,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8
+0,lanternfish,5,0,1,1,2,1,0,0,0,0
+1,lanternfish,5,1,1,2,1,0,0,0,0,0
+2,lanternfish,6,1,2,1,0,0,0,1,0,1
+"
+        );
+    }
+}
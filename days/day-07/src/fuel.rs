@@ -0,0 +1,260 @@
+use anyhow::Result;
+use std::str::FromStr;
+
+/// Fuel a crab burns to move a given distance
+pub trait FuelCost {
+    fn cost(&self, distance: i128) -> i128;
+
+    /// Whether the cost is convex and never decreases with distance, which makes the total cost
+    /// convex in the final position so it can be minimized without checking every position
+    fn is_convex(&self) -> bool {
+        false
+    }
+}
+
+/// One unit of fuel per step
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: i128) -> i128 {
+        distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Every step costs one more than the previous
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: i128) -> i128 {
+        (distance * (distance + 1)) / 2
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// The square of the distance
+pub struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: i128) -> i128 {
+        distance * distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Another cost that never goes above `cap`, which breaks convexity
+pub struct Capped<C> {
+    pub inner: C,
+    pub cap: i128,
+}
+
+impl<C: FuelCost> FuelCost for Capped<C> {
+    fn cost(&self, distance: i128) -> i128 {
+        std::cmp::min(self.inner.cost(distance), self.cap)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(i128),
+    Distance,
+    Negate(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Subtract(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+    Divide(Box<Expr>, Box<Expr>),
+    Power(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Saturates instead of overflowing, dividing by zero gives the largest cost
+    fn evaluate(&self, distance: i128) -> i128 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Distance => distance,
+            Expr::Negate(inner) => inner.evaluate(distance).saturating_neg(),
+            Expr::Add(left, right) => left
+                .evaluate(distance)
+                .saturating_add(right.evaluate(distance)),
+            Expr::Subtract(left, right) => left
+                .evaluate(distance)
+                .saturating_sub(right.evaluate(distance)),
+            Expr::Multiply(left, right) => left
+                .evaluate(distance)
+                .saturating_mul(right.evaluate(distance)),
+            Expr::Divide(left, right) => left
+                .evaluate(distance)
+                .checked_div(right.evaluate(distance))
+                .unwrap_or(i128::MAX),
+            Expr::Power(base, exponent) => {
+                let exponent = exponent.evaluate(distance).clamp(0, u32::MAX as i128);
+                base.evaluate(distance).saturating_pow(exponent as u32)
+            }
+        }
+    }
+}
+
+/// Recursive descent over `+ - * / ^`, parentheses, integers and the distance `d`
+struct Parser<'a> {
+    tokens: std::iter::Peekable<std::str::CharIndices<'a>>,
+    source: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.tokens.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.tokens.peek().map(|(_, c)| *c)
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut expr = self.product()?;
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.tokens.next();
+                    expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+                }
+                Some('-') => {
+                    self.tokens.next();
+                    expr = Expr::Subtract(Box::new(expr), Box::new(self.product()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.tokens.next();
+                    expr = Expr::Multiply(Box::new(expr), Box::new(self.unary()?));
+                }
+                Some('/') => {
+                    self.tokens.next();
+                    expr = Expr::Divide(Box::new(expr), Box::new(self.unary()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    /// Negation binds looser than powers, so -2^2 is -4
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some('-') {
+            self.tokens.next();
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr> {
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.tokens.next();
+            // Right associative, so 2^3^2 is 2^9
+            return Ok(Expr::Power(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some('(') => {
+                self.tokens.next();
+                let expr = self.sum()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.tokens.next();
+                        Ok(expr)
+                    }
+                    _ => anyhow::bail!("Missing closing parenthesis in {:?}", self.source),
+                }
+            }
+            Some('d') => {
+                self.tokens.next();
+                Ok(Expr::Distance)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some((_, digit)) = self.tokens.next_if(|(_, c)| c.is_ascii_digit()) {
+                    number.push(digit);
+                }
+                Ok(Expr::Number(number.parse()?))
+            }
+            Some(c) => {
+                let (position, _) = self.tokens.peek().cloned().unwrap_or((0, c));
+                anyhow::bail!("Unexpected {:?} at {} in {:?}", c, position, self.source)
+            }
+            None => anyhow::bail!("Unexpected end of {:?}", self.source),
+        }
+    }
+}
+
+/// A cost written as an expression of the distance `d`, like `d * (d + 1) / 2`. Convexity
+/// can't be inferred, so it has to be declared to skip the full scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    expr: Expr,
+    convex: bool,
+}
+
+impl Expression {
+    pub fn declare_convex(mut self, convex: bool) -> Self {
+        self.convex = convex;
+        self
+    }
+}
+
+impl FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: source.char_indices().peekable(),
+            source,
+        };
+        let expr = parser.sum()?;
+        if let Some(c) = parser.peek() {
+            anyhow::bail!("Unexpected trailing {:?} in {:?}", c, source);
+        }
+        Ok(Self {
+            expr,
+            convex: false,
+        })
+    }
+}
+
+impl FuelCost for Expression {
+    fn cost(&self, distance: i128) -> i128 {
+        self.expr.evaluate(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.convex
+    }
+}
+
+impl<C: FuelCost + ?Sized> FuelCost for Box<C> {
+    fn cost(&self, distance: i128) -> i128 {
+        self.as_ref().cost(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.as_ref().is_convex()
+    }
+}
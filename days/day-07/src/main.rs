@@ -1,6 +1,10 @@
+mod fuel;
+
 use anyhow::{Context, Result};
+use fuel::{Capped, Expression, FuelCost, Linear, Quadratic, Triangular};
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::ops::RangeInclusive;
 use std::path::Path;
 
 const CURRENT_FILE: &str = file!();
@@ -28,9 +32,14 @@ fn parse_first_line(lines: &[String]) -> Result<Vec<i64>> {
     inputs
 }
 
-fn part_one(lines: &[String]) -> Result<i64> {
-    let inputs = parse_first_line(lines)?;
+fn total_cost(inputs: &[i64], final_position: i64, cost: &dyn FuelCost) -> i128 {
+    inputs
+        .iter()
+        .map(|val| cost.cost((*val as i128 - final_position as i128).abs()))
+        .fold(0, i128::saturating_add)
+}
 
+fn horizontal_range(inputs: &[i64]) -> Result<(i64, i64)> {
     let min_horizontal = *inputs
         .iter()
         .min()
@@ -39,43 +48,226 @@ fn part_one(lines: &[String]) -> Result<i64> {
         .iter()
         .max()
         .ok_or_else(|| anyhow::anyhow!("Couldn't determine max"))?;
-    let final_costs = (min_horizontal..=max_horizontal).map(|final_position| {
-        inputs
+    Ok((min_horizontal, max_horizontal))
+}
+
+/// The cheapest way to line the crabs up
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alignment {
+    cost: i128,
+    /// Every position reaching `cost`, as ascending runs of consecutive positions
+    tied: Vec<RangeInclusive<i64>>,
+}
+
+impl Alignment {
+    /// Keeps the cheapest of `(position, cost)` candidates given in ascending position order
+    fn from_candidates<I: IntoIterator<Item = (i64, i128)>>(candidates: I) -> Result<Self> {
+        let mut best: Option<Self> = None;
+        for (position, cost) in candidates {
+            match best.as_mut() {
+                Some(best) if cost == best.cost => match best.tied.last_mut() {
+                    Some(run) if *run.end() + 1 == position => *run = *run.start()..=position,
+                    _ => best.tied.push(position..=position),
+                },
+                Some(best) if cost > best.cost => {}
+                _ => {
+                    best = Some(Self {
+                        cost,
+                        tied: vec![position..=position],
+                    })
+                }
+            }
+        }
+        best.ok_or_else(|| anyhow::anyhow!("Could not determine final min cost"))
+    }
+
+    /// The leftmost optimal position
+    fn position(&self) -> i64 {
+        *self.tied[0].start()
+    }
+}
+
+/// Fuel each crab burns to reach `final_position`, in input order
+fn fuel_breakdown(inputs: &[i64], final_position: i64, cost: &dyn FuelCost) -> Vec<i128> {
+    inputs
+        .iter()
+        .map(|val| cost.cost((*val as i128 - final_position as i128).abs()))
+        .collect()
+}
+
+/// Tries every position between the outermost crabs, only feasible for small ranges
+fn brute_force_alignment(inputs: &[i64], cost: &dyn FuelCost) -> Result<Alignment> {
+    let (min_horizontal, max_horizontal) = horizontal_range(inputs)?;
+
+    Alignment::from_candidates(
+        (min_horizontal..=max_horizontal)
+            .map(|final_position| (final_position, total_cost(inputs, final_position, cost))),
+    )
+}
+
+/// Binary searches for the first position where the total stops decreasing when the cost is
+/// convex, and falls back to trying every position otherwise
+fn minimize_alignment(inputs: &[i64], cost: &dyn FuelCost) -> Result<Alignment> {
+    if !cost.is_convex() {
+        return brute_force_alignment(inputs, cost);
+    }
+
+    let (mut low, mut high) = horizontal_range(inputs)?;
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if total_cost(inputs, middle + 1, cost) >= total_cost(inputs, middle, cost) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    let first = low;
+    let min_cost = total_cost(inputs, first, cost);
+
+    // Ties form a single plateau to the right of the first minimum
+    let (_, mut high) = horizontal_range(inputs)?;
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if total_cost(inputs, middle, cost) == min_cost {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    Ok(Alignment {
+        cost: min_cost,
+        tied: vec![first..=low],
+    })
+}
+
+/// The median minimizes the sum of absolute distances, with every position between the two
+/// middle crabs tying for an even number of crabs
+fn median_alignment(inputs: &[i64]) -> Result<Alignment> {
+    let mut sorted = inputs.to_vec();
+    if sorted.is_empty() {
+        anyhow::bail!("Couldn't determine median");
+    }
+    let middle = sorted.len() / 2;
+    let (_, upper, _) = sorted.select_nth_unstable(middle);
+    let upper = *upper;
+    let lower = if inputs.len().is_multiple_of(2) {
+        *sorted[..middle]
             .iter()
-            .map(move |val| (val - final_position).abs())
-            .sum()
-    });
+            .max()
+            .ok_or_else(|| anyhow::anyhow!("Couldn't determine median"))?
+    } else {
+        upper
+    };
 
-    final_costs
-        .min()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine final min cost"))
+    Ok(Alignment {
+        cost: total_cost(inputs, upper, &Linear),
+        tied: vec![lower..=upper],
+    })
 }
 
-fn part_two(lines: &[String]) -> Result<i64> {
+/// The triangular cost is convex and its real minimum lies within half a step of the mean, so
+/// the best integer positions are at most one step further out on either side
+fn mean_alignment(inputs: &[i64]) -> Result<Alignment> {
+    if inputs.is_empty() {
+        anyhow::bail!("Couldn't determine mean");
+    }
+    let sum = inputs.iter().map(|val| *val as i128).sum::<i128>();
+    let mean = sum.div_euclid(inputs.len() as i128) as i64;
+
+    Alignment::from_candidates((mean - 1..=mean + 2).map(|final_position| {
+        (
+            final_position,
+            total_cost(inputs, final_position, &Triangular),
+        )
+    }))
+}
+
+fn part_one(lines: &[String], brute_force: bool) -> Result<Alignment> {
     let inputs = parse_first_line(lines)?;
+    let alignment = median_alignment(&inputs)?;
 
-    let min_horizontal = *inputs
-        .iter()
-        .min()
-        .ok_or_else(|| anyhow::anyhow!("Couldn't determine min"))?;
-    let max_horizontal = *inputs
-        .iter()
-        .max()
-        .ok_or_else(|| anyhow::anyhow!("Couldn't determine max"))?;
+    if brute_force {
+        let expected = brute_force_alignment(&inputs, &Linear)?;
+        anyhow::ensure!(
+            alignment == expected,
+            "Median alignment {:?} doesn't match brute force alignment {:?}",
+            alignment,
+            expected
+        );
+    }
+
+    Ok(alignment)
+}
+
+fn part_two(lines: &[String], brute_force: bool) -> Result<Alignment> {
+    let inputs = parse_first_line(lines)?;
+    let alignment = mean_alignment(&inputs)?;
+
+    if brute_force {
+        let expected = brute_force_alignment(&inputs, &Triangular)?;
+        anyhow::ensure!(
+            alignment == expected,
+            "Mean alignment {:?} doesn't match brute force alignment {:?}",
+            alignment,
+            expected
+        );
+    }
+
+    Ok(alignment)
+}
 
-    let final_costs = (min_horizontal..=max_horizontal).map(|final_position| {
-        inputs
+/// Writes the total cost of every position between the outermost crabs for each named cost
+fn write_cost_curve<W: std::io::Write>(
+    inputs: &[i64],
+    costs: &[(&str, &dyn FuelCost)],
+    writer: W,
+) -> Result<()> {
+    let (min_horizontal, max_horizontal) = horizontal_range(inputs)?;
+    let mut writer = csv::Writer::from_writer(writer);
+
+    let mut header = vec!["position"];
+    header.extend(costs.iter().map(|(name, _)| *name));
+    writer.write_record(&header)?;
+
+    for final_position in min_horizontal..=max_horizontal {
+        let mut record = vec![final_position.to_string()];
+        record.extend(
+            costs
+                .iter()
+                .map(|(_, cost)| total_cost(inputs, final_position, *cost).to_string()),
+        );
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn print_alignment(inputs: &[i64], alignment: &Alignment, cost: &dyn FuelCost, breakdown: bool) {
+    println!("{:?}", alignment.cost);
+    println!(
+        "  at position {} (tied: {})",
+        alignment.position(),
+        alignment
+            .tied
             .iter()
-            .map(move |val| {
-                let abs_fuel_cost = (val - final_position).abs();
-                (abs_fuel_cost * (abs_fuel_cost + 1)) / 2
+            .map(|run| if run.start() == run.end() {
+                run.start().to_string()
+            } else {
+                format!("{}..={}", run.start(), run.end())
             })
-            .sum()
-    });
-
-    final_costs
-        .min()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine final min cost"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if breakdown {
+        for (crab, fuel) in inputs
+            .iter()
+            .zip(fuel_breakdown(inputs, alignment.position(), cost))
+        {
+            println!("  crab at {} burns {}", crab, fuel);
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -85,8 +277,183 @@ fn main() -> Result<()> {
         .join(INPUT_FILE_PATH);
 
     let input = read_lines(&input_path)?;
-    println!("{:?}", part_one(&input)?);
-    println!("{:?}", part_two(&input)?);
+    let args = std::env::args().collect::<Vec<_>>();
+    let brute_force = args.iter().any(|arg| arg == "--brute-force");
+    let breakdown = args.iter().any(|arg| arg == "--breakdown");
+    let inputs = parse_first_line(&input)?;
+    print_alignment(&inputs, &part_one(&input, brute_force)?, &Linear, breakdown);
+    print_alignment(
+        &inputs,
+        &part_two(&input, brute_force)?,
+        &Triangular,
+        breakdown,
+    );
+
+    if let Some(position) = args.iter().position(|arg| arg == "--cost") {
+        let source = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--cost needs a cost name or an expression of d"))?;
+        let cost: Box<dyn FuelCost> = match source.as_str() {
+            "linear" => Box::new(Linear),
+            "triangular" => Box::new(Triangular),
+            "quadratic" => Box::new(Quadratic),
+            _ => Box::new(
+                source
+                    .parse::<Expression>()?
+                    .declare_convex(args.iter().any(|arg| arg == "--convex")),
+            ),
+        };
+        let cost = match args.iter().position(|arg| arg == "--cap") {
+            Some(position) => {
+                let cap = args
+                    .get(position + 1)
+                    .ok_or_else(|| anyhow::anyhow!("--cap needs a value"))?
+                    .parse::<i128>()?;
+                Box::new(Capped { inner: cost, cap })
+            }
+            None => cost,
+        };
+        let alignment = minimize_alignment(&inputs, cost.as_ref())?;
+        print_alignment(&inputs, &alignment, cost.as_ref(), breakdown);
+    }
+
+    if let Some(position) = args.iter().position(|arg| arg == "--curve") {
+        let path = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--curve needs an output path"))?;
+        let file = File::create(path).with_context(|| format!("Could not create {}", path))?;
+        write_cost_curve(
+            &inputs,
+            &[("linear", &Linear), ("triangular", &Triangular)],
+            file,
+        )?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: [i64; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+    #[test]
+    fn matches_example() {
+        let alignment = median_alignment(&EXAMPLE).unwrap();
+        assert_eq!((alignment.cost, alignment.position()), (37, 2));
+        let alignment = mean_alignment(&EXAMPLE).unwrap();
+        assert_eq!((alignment.cost, alignment.position()), (168, 5));
+        assert_eq!(
+            fuel_breakdown(&EXAMPLE, 2, &Linear),
+            vec![14, 1, 0, 2, 2, 0, 5, 1, 0, 12]
+        );
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let inputs = [
+            vec![0, 1000],
+            vec![3],
+            vec![-7, 2, 2, 9, 40, -13],
+            vec![1, 1, 1, 1, 1, 1, 1, 100],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 7],
+            vec![0, 1],
+        ];
+        for inputs in inputs {
+            assert_eq!(
+                median_alignment(&inputs).unwrap(),
+                brute_force_alignment(&inputs, &Linear).unwrap()
+            );
+            assert_eq!(
+                mean_alignment(&inputs).unwrap(),
+                brute_force_alignment(&inputs, &Triangular).unwrap()
+            );
+            for cost in [&Linear as &dyn FuelCost, &Triangular, &Quadratic] {
+                assert_eq!(
+                    minimize_alignment(&inputs, cost).unwrap(),
+                    brute_force_alignment(&inputs, cost).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reports_every_tied_position() {
+        let alignment = median_alignment(&[0, 1000]).unwrap();
+        assert_eq!(alignment.tied, vec![0..=1000]);
+
+        let alignment = mean_alignment(&[0, 1]).unwrap();
+        assert_eq!(alignment.tied, vec![0..=1]);
+
+        // Two separate clusters tie when the cost is capped
+        let capped = Capped {
+            inner: Linear,
+            cap: 1,
+        };
+        let alignment = minimize_alignment(&[0, 0, 10, 10], &capped).unwrap();
+        assert_eq!(alignment.tied, vec![0..=0, 10..=10]);
+    }
+
+    #[test]
+    fn handles_billion_wide_ranges() {
+        let inputs = [0, 3_000_000_000, 4_000_000_000, 4_000_000_001];
+        assert_eq!(median_alignment(&inputs).unwrap().cost, 5_000_000_001);
+
+        // Two crabs meet halfway, each spending 1 + 2 + ... + 10^9 = 10^9 * (10^9 + 1) / 2
+        let alignment = mean_alignment(&[0, 2_000_000_000]).unwrap();
+        assert_eq!(alignment.cost, 1_000_000_001_000_000_000);
+        assert_eq!(alignment.tied, vec![1_000_000_000..=1_000_000_000]);
+    }
+
+    #[test]
+    fn expressions_match_builtin_costs() {
+        let triangular = "d * (d + 1) / 2"
+            .parse::<Expression>()
+            .unwrap()
+            .declare_convex(true);
+        assert_eq!(minimize_alignment(&EXAMPLE, &triangular).unwrap().cost, 168);
+
+        let quadratic = "d^2".parse::<Expression>().unwrap();
+        assert!(!quadratic.is_convex());
+        assert_eq!(
+            minimize_alignment(&EXAMPLE, &quadratic).unwrap(),
+            minimize_alignment(&EXAMPLE, &Quadratic).unwrap()
+        );
+
+        assert_eq!("-2 ^ 2 + 3 * d".parse::<Expression>().unwrap().cost(1), -1);
+        assert!("d +".parse::<Expression>().is_err());
+        assert!("(d".parse::<Expression>().is_err());
+        assert!("d x".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn capped_costs_fall_back_to_a_full_scan() {
+        let capped = Capped {
+            inner: Quadratic,
+            cap: 10,
+        };
+        assert!(!capped.is_convex());
+        // With a cap every far away crab costs the same, so the cluster around 2 wins
+        assert_eq!(minimize_alignment(&EXAMPLE, &capped).unwrap().cost, 40);
+        assert_eq!(
+            minimize_alignment(&EXAMPLE, &capped).unwrap(),
+            brute_force_alignment(&EXAMPLE, &capped).unwrap()
+        );
+    }
+
+    #[test]
+    fn cost_curve_as_csv() {
+        let mut output = Vec::new();
+        write_cost_curve(
+            &[0, 2],
+            &[("linear", &Linear), ("triangular", &Triangular)],
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "position,linear,triangular\n0,2,3\n1,2,2\n2,2,3\n"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::Result;
+
+/// Lit segments as bits, `a` being the lowest
+pub type Segments = u16;
+
+/// Displays can have up to sixteen segments, wired `a` to `p`
+pub const MAX_SEGMENTS: usize = 16;
+
+/// Segments lit for each digit on a correctly wired seven segment display
+pub const DIGIT_SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+/// Hexadecimal A to F after the decimal digits, as `A b C d E F`
+pub const HEX_SEGMENTS: [&str; 6] = ["abcdef", "bdefg", "abeg", "cdefg", "abdeg", "abde"];
+
+/// Symbols used for glyph values in glyph tables
+const SYMBOLS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub fn parse_segments(pattern: &str, segment_count: usize) -> Option<Segments> {
+    pattern.bytes().try_fold(0, |mask: Segments, wire| {
+        let wire = wire.checked_sub(b'a')? as usize;
+        if wire >= segment_count {
+            return None;
+        }
+        Some(mask | 1 << wire)
+    })
+}
+
+/// The glyphs a display can show, where the position of a glyph is the digit it stands for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphSet {
+    segment_count: usize,
+    glyphs: Vec<Segments>,
+    /// Digit for every possible combination of lit segments
+    table: Vec<Option<u8>>,
+    /// Whether these are the puzzle's own glyphs, which have a shortcut for deducing the wiring
+    seven_segment_decimal: bool,
+}
+
+impl GlyphSet {
+    pub fn new(segment_count: usize, glyphs: Vec<Segments>) -> Result<Self> {
+        if segment_count > MAX_SEGMENTS {
+            anyhow::bail!("Displays can't have more than {} segments", MAX_SEGMENTS);
+        }
+        if glyphs.len() < 2 || glyphs.len() > SYMBOLS.len() {
+            anyhow::bail!(
+                "Displays need between 2 and {} glyphs, got {}",
+                SYMBOLS.len(),
+                glyphs.len()
+            );
+        }
+
+        let mut table = vec![None; 1 << segment_count];
+        for (digit, glyph) in glyphs.iter().enumerate() {
+            let entry = table
+                .get_mut(*glyph as usize)
+                .ok_or_else(|| anyhow::anyhow!("Glyph {} uses unknown segments", digit))?;
+            if let Some(other) = entry {
+                anyhow::bail!("Glyphs {} and {} look the same", other, digit);
+            }
+            *entry = Some(digit as u8);
+        }
+
+        let seven_segment_decimal = segment_count == 7
+            && glyphs.len() == DIGIT_SEGMENTS.len()
+            && glyphs
+                .iter()
+                .zip(DIGIT_SEGMENTS)
+                .all(|(glyph, segments)| parse_segments(segments, 7) == Some(*glyph));
+
+        Ok(Self {
+            segment_count,
+            glyphs,
+            table,
+            seven_segment_decimal,
+        })
+    }
+
+    fn from_segments<'a, I: IntoIterator<Item = &'a str>>(
+        segment_count: usize,
+        glyphs: I,
+    ) -> Result<Self> {
+        let glyphs = glyphs
+            .into_iter()
+            .map(|segments| {
+                parse_segments(segments, segment_count)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid segments {:?}", segments))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(segment_count, glyphs)
+    }
+
+    pub fn seven_segment() -> Self {
+        Self::from_segments(7, DIGIT_SEGMENTS).expect("Decimal glyphs are distinct")
+    }
+
+    pub fn seven_segment_hex() -> Self {
+        Self::from_segments(7, DIGIT_SEGMENTS.into_iter().chain(HEX_SEGMENTS))
+            .expect("Hexadecimal glyphs are distinct")
+    }
+
+    /// Reads a table with one `<symbol> <segments>` line per glyph, like `A abcdef`, where the
+    /// symbols `0-9A-Z` give the value of each glyph and have to cover `0` up to the radix
+    pub fn from_table(table: &str) -> Result<Self> {
+        let mut glyphs: Vec<Option<&str>> = vec![];
+        for line in table.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (symbol, segments) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow::anyhow!("Could not split glyph line {:?}", line))?;
+            let value = SYMBOLS
+                .find(&symbol.to_ascii_uppercase())
+                .filter(|_| symbol.len() == 1)
+                .ok_or_else(|| anyhow::anyhow!("Unknown glyph symbol {:?}", symbol))?;
+            if glyphs.len() <= value {
+                glyphs.resize(value + 1, None);
+            }
+            glyphs[value] = Some(segments.trim());
+        }
+
+        let glyphs = glyphs
+            .into_iter()
+            .enumerate()
+            .map(|(value, glyph)| {
+                glyph
+                    .ok_or_else(|| anyhow::anyhow!("Missing glyph for {}", &SYMBOLS[value..=value]))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let segment_count = glyphs
+            .iter()
+            .flat_map(|glyph| glyph.bytes())
+            .max()
+            .map_or(0, |wire| (wire.saturating_sub(b'a') + 1) as usize);
+        Self::from_segments(segment_count, glyphs)
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segment_count
+    }
+
+    pub fn glyphs(&self) -> &[Segments] {
+        &self.glyphs
+    }
+
+    /// Base the output digits are read in
+    pub fn radix(&self) -> u64 {
+        self.glyphs.len() as u64
+    }
+
+    pub fn digit(&self, segments: Segments) -> Option<u8> {
+        self.table.get(segments as usize).cloned().flatten()
+    }
+
+    pub fn is_seven_segment_decimal(&self) -> bool {
+        self.seven_segment_decimal
+    }
+}
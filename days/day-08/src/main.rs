@@ -1,4 +1,9 @@
+mod glyphs;
+#[cfg(test)]
+mod set_based;
+
 use anyhow::{Context, Result};
+use glyphs::{parse_segments, GlyphSet, Segments, MAX_SEGMENTS};
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::{self, BufRead};
@@ -42,119 +47,275 @@ fn part_one(lines: &[String]) -> Result<usize> {
         .count())
 }
 
-fn part_two(lines: &[String]) -> Result<i32> {
-    let mut final_output_value = 0;
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Decoding {
+    Value(u64),
+    /// Several wirings fit the patterns but read the output differently
+    Ambiguous(Vec<u64>),
+    /// No wiring fits the patterns
+    Inconsistent,
+    /// The output reads as a value too large for a `u64`
+    Overflow,
+}
 
-    for line in lines {
-        let (signal, outputs) = parse_line(line)?;
+/// Segment each wire is connected to, indexed by wire
+type Wiring = [u8; MAX_SEGMENTS];
 
-        let one_pattern = signal
-            .iter()
-            .find(|val| val.len() == 2)
-            .ok_or_else(|| anyhow::anyhow!("Could not find pattern for one"))?;
-        let four_pattern = signal
-            .iter()
-            .find(|val| val.len() == 4)
-            .ok_or_else(|| anyhow::anyhow!("Could not find pattern for four"))?;
-        let seven_pattern = signal
-            .iter()
-            .find(|val| val.len() == 3)
-            .ok_or_else(|| anyhow::anyhow!("Could not find pattern for seven"))?;
-        let eight_pattern = signal
+/// Moves every lit wire to the segment it's connected to
+fn rewire(pattern: Segments, wiring: &Wiring) -> Segments {
+    (0..MAX_SEGMENTS)
+        .filter(|wire| pattern & 1 << wire != 0)
+        .fold(0, |mask, wire| mask | 1 << wiring[wire])
+}
+
+/// With all ten digits present every segment is lit a distinctive number of times, apart from
+/// `a`/`c` and `d`/`g`, which are told apart by whether they're part of one and four
+fn deduce_wiring(signal: &[Segments]) -> Option<Wiring> {
+    let mut distinct = [0; 10];
+    if signal.len() != distinct.len() {
+        return None;
+    }
+    distinct.copy_from_slice(signal);
+    distinct.sort_unstable();
+    if distinct.windows(2).any(|pair| pair[0] == pair[1]) {
+        return None;
+    }
+    let one = *distinct.iter().find(|mask| mask.count_ones() == 2)?;
+    let four = *distinct.iter().find(|mask| mask.count_ones() == 4)?;
+
+    let mut wiring = [0; MAX_SEGMENTS];
+    for (wire, segment) in wiring.iter_mut().enumerate().take(7) {
+        let bit = 1 << wire;
+        let frequency = distinct.iter().filter(|mask| *mask & bit != 0).count();
+        *segment = match (frequency, one & bit != 0, four & bit != 0) {
+            (4, _, _) => 4,
+            (6, _, _) => 1,
+            (9, _, _) => 5,
+            (8, true, _) => 2,
+            (8, false, _) => 0,
+            (7, _, true) => 3,
+            (7, _, false) => 6,
+            _ => return None,
+        };
+    }
+    Some(wiring)
+}
+
+/// Finds every wiring of scrambled wires to segments that turns all patterns into glyphs by
+/// assigning one wire at a time, dropping partial wirings that can't become a glyph
+struct WiringSolver<'a> {
+    glyphs: &'a GlyphSet,
+    patterns: Vec<Segments>,
+    wires: Vec<usize>,
+}
+
+impl<'a> WiringSolver<'a> {
+    fn new(glyphs: &'a GlyphSet, patterns: &[Segments]) -> Self {
+        let lit = patterns.iter().fold(0, |lit, pattern| lit | pattern);
+        Self {
+            glyphs,
+            patterns: patterns.to_vec(),
+            wires: (0..glyphs.segment_count())
+                .filter(|wire| lit & 1 << wire != 0)
+                .collect(),
+        }
+    }
+
+    /// Segments already known for a pattern must fit in a glyph with as many segments
+    fn could_be_glyph(&self, pattern: Segments, wiring: &Wiring, assigned: Segments) -> bool {
+        let known = rewire(pattern & assigned, wiring);
+        self.glyphs
+            .glyphs()
             .iter()
-            .find(|val| val.len() == 7)
-            .ok_or_else(|| anyhow::anyhow!("Could not find pattern for eight"))?;
-
-        let one_pattern: HashSet<char> = HashSet::from_iter(one_pattern.chars());
-        let four_pattern: HashSet<char> = HashSet::from_iter(four_pattern.chars());
-        let seven_pattern: HashSet<char> = HashSet::from_iter(seven_pattern.chars());
-        let eight_pattern: HashSet<char> = HashSet::from_iter(eight_pattern.chars());
-
-        let six_segment_candidates = signal.iter().filter(|val| val.len() == 6);
-        let five_segment_candidates = signal.iter().filter(|val| val.len() == 5);
-
-        let nine_pattern = HashSet::from_iter(
-            six_segment_candidates
-                .clone()
-                .find(|val| HashSet::from_iter(val.chars()).is_superset(&four_pattern))
-                .unwrap()
-                .chars(),
-        );
-        let zero_pattern = HashSet::from_iter(
-            six_segment_candidates
-                .clone()
-                .find(|val| {
-                    HashSet::from_iter(val.chars()).is_superset(&one_pattern)
-                        && HashSet::from_iter(val.chars()) != nine_pattern
-                })
-                .unwrap()
-                .chars(),
-        );
-        let six_pattern = HashSet::from_iter(
-            six_segment_candidates
-                .clone()
-                .find(|val| {
-                    HashSet::from_iter(val.chars()) != zero_pattern
-                        && HashSet::from_iter(val.chars()) != nine_pattern
-                })
-                .unwrap()
-                .chars(),
-        );
+            .any(|glyph| glyph.count_ones() == pattern.count_ones() && known & !glyph == 0)
+    }
 
-        let three_pattern = HashSet::from_iter(
-            five_segment_candidates
-                .clone()
-                .find(|val| HashSet::from_iter(val.chars()).is_superset(&seven_pattern))
-                .unwrap()
-                .chars(),
-        );
+    fn search(
+        &self,
+        depth: usize,
+        wiring: &mut Wiring,
+        assigned: Segments,
+        used: Segments,
+        found: &mut Vec<Wiring>,
+    ) {
+        let wire = match self.wires.get(depth) {
+            Some(wire) => *wire,
+            None => {
+                found.push(*wiring);
+                return;
+            }
+        };
 
-        let five_pattern = HashSet::from_iter(
-            five_segment_candidates
-                .clone()
-                .find(|val| HashSet::from_iter(val.chars()).is_subset(&six_pattern))
-                .unwrap()
-                .chars(),
-        );
+        for segment in (0..self.glyphs.segment_count()).filter(|segment| used & 1 << segment == 0) {
+            wiring[wire] = segment as u8;
+            let assigned = assigned | 1 << wire;
+            if self
+                .patterns
+                .iter()
+                .all(|pattern| self.could_be_glyph(*pattern, wiring, assigned))
+            {
+                self.search(depth + 1, wiring, assigned, used | 1 << segment, found);
+            }
+        }
+    }
 
-        let two_pattern = HashSet::from_iter(
-            five_segment_candidates
-                .clone()
-                .find(|val| {
-                    HashSet::from_iter(val.chars()) != three_pattern
-                        && HashSet::from_iter(val.chars()) != five_pattern
-                })
-                .unwrap()
-                .chars(),
-        );
+    fn wirings(&self) -> Vec<Wiring> {
+        let mut found = vec![];
+        self.search(0, &mut [0; MAX_SEGMENTS], 0, 0, &mut found);
+        found
+    }
+}
 
-        let patterns = [
-            (one_pattern, 1),
-            (seven_pattern, 7),
-            (four_pattern, 4),
-            (two_pattern, 2),
-            (five_pattern, 5),
-            (three_pattern, 3),
-            (six_pattern, 6),
-            (nine_pattern, 9),
-            (zero_pattern, 0),
-            (eight_pattern, 8),
-        ];
+/// Reads the output in the radix of the glyph set, `None` if a pattern isn't a glyph
+fn read_output(glyphs: &GlyphSet, outputs: &[Segments], wiring: &Wiring) -> Option<Decoding> {
+    let mut value: u64 = 0;
+    for output in outputs {
+        let digit = glyphs.digit(rewire(*output, wiring))?;
+        value = match value
+            .checked_mul(glyphs.radix())
+            .and_then(|value| value.checked_add(digit as u64))
+        {
+            Some(value) => value,
+            None => return Some(Decoding::Overflow),
+        };
+    }
+    Some(Decoding::Value(value))
+}
 
-        let mut final_number = 0;
-        for (power, num) in outputs.iter().enumerate() {
-            let (_, value) = patterns
+/// Decodes the output of one display, which works with missing signal patterns as long as
+/// every fitting wiring reads the output the same way
+fn decode_entry(glyphs: &GlyphSet, signal: &[&str], outputs: &[&str]) -> Decoding {
+    let parse = |patterns: &[&str]| -> Option<Vec<Segments>> {
+        patterns
+            .iter()
+            .map(|pattern| parse_segments(pattern, glyphs.segment_count()))
+            .collect()
+    };
+    let (signal, outputs) = match (parse(signal), parse(outputs)) {
+        (Some(signal), Some(outputs)) => (signal, outputs),
+        _ => return Decoding::Inconsistent,
+    };
+    if glyphs.is_seven_segment_decimal() {
+        if let Some(wiring) = deduce_wiring(&signal) {
+            if signal
                 .iter()
-                .find(|(mapping, _)| HashSet::from_iter(num.chars()).eq(mapping))
-                .ok_or_else(|| anyhow::anyhow!("Couldn't find the number captain"))?;
-            final_number += value * (10_i32.pow(3 - power as u32));
+                .all(|pattern| glyphs.digit(rewire(*pattern, &wiring)).is_some())
+            {
+                if let Some(decoding) = read_output(glyphs, &outputs, &wiring) {
+                    return decoding;
+                }
+            }
+        }
+    }
+
+    let patterns: Vec<Segments> = signal.iter().chain(&outputs).cloned().collect();
+    let mut values = vec![];
+    for wiring in WiringSolver::new(glyphs, &patterns).wirings() {
+        match read_output(glyphs, &outputs, &wiring) {
+            Some(Decoding::Value(value)) => values.push(value),
+            Some(decoding) => return decoding,
+            None => {}
+        }
+    }
+    values.sort_unstable();
+    values.dedup();
+
+    match values.as_slice() {
+        [] => Decoding::Inconsistent,
+        [value] => Decoding::Value(*value),
+        _ => Decoding::Ambiguous(values),
+    }
+}
+
+/// Decodes a whole `patterns | outputs` line straight from its bytes when all ten patterns of
+/// the puzzle's display are there, only splitting it up for the wiring search otherwise
+fn decode_line(glyphs: &GlyphSet, line: &str) -> Result<Decoding> {
+    let fast = || -> Option<u64> {
+        if !glyphs.is_seven_segment_decimal() {
+            return None;
+        }
+        let mut patterns = [0; 10];
+        let mut count = 0;
+        let mut bytes = line.bytes();
+        let mut mask: Segments = 0;
+        for byte in &mut bytes {
+            match byte {
+                b'a'..=b'g' => mask |= 1 << (byte - b'a'),
+                b' ' | b'|' => {
+                    if mask != 0 {
+                        *patterns.get_mut(count)? = mask;
+                        count += 1;
+                        mask = 0;
+                    }
+                    if byte == b'|' {
+                        break;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        let wiring = deduce_wiring(&patterns[..count])?;
+        let mut translation = [None; 128];
+        for pattern in patterns {
+            translation[pattern as usize] = Some(glyphs.digit(rewire(pattern, &wiring))?);
+        }
+
+        let mut value: u64 = 0;
+        for byte in bytes.chain([b' ']) {
+            match byte {
+                b'a'..=b'g' => mask |= 1 << (byte - b'a'),
+                b' ' => {
+                    if mask != 0 {
+                        let digit = translation[mask as usize]?;
+                        value = value.checked_mul(10)?.checked_add(digit as u64)?;
+                        mask = 0;
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(value)
+    };
+
+    match fast() {
+        Some(value) => Ok(Decoding::Value(value)),
+        None => {
+            let (signal, outputs) = parse_line(line)?;
+            Ok(decode_entry(glyphs, &signal, &outputs))
+        }
+    }
+}
+
+fn decode_all(lines: &[String], glyphs: &GlyphSet) -> Result<u64> {
+    let mut final_output_value: u64 = 0;
+    let mut failures = vec![];
+
+    for (number, line) in lines.iter().enumerate() {
+        match decode_line(glyphs, line)? {
+            Decoding::Value(value) => {
+                final_output_value = final_output_value
+                    .checked_add(value)
+                    .ok_or_else(|| anyhow::anyhow!("Sum of outputs overflowed"))?
+            }
+            Decoding::Ambiguous(values) => {
+                failures.push(format!("line {} is ambiguous: {:?}", number + 1, values))
+            }
+            Decoding::Inconsistent => failures.push(format!("line {} is inconsistent", number + 1)),
+            Decoding::Overflow => failures.push(format!("line {} is too long", number + 1)),
         }
-        final_output_value += final_number;
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!("Could not decode every entry:\n{}", failures.join("\n"));
     }
 
     Ok(final_output_value)
 }
 
+fn part_two(lines: &[String]) -> Result<u64> {
+    decode_all(lines, &GlyphSet::seven_segment())
+}
+
 fn main() -> Result<()> {
     let input_path = Path::new(CURRENT_FILE)
         .parent()
@@ -165,5 +326,172 @@ fn main() -> Result<()> {
     println!("{:?}", part_one(&input)?);
     println!("{:?}", part_two(&input)?);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    let glyphs = match args.iter().position(|arg| arg == "--glyphs") {
+        Some(position) => {
+            let path = args
+                .get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--glyphs needs a glyph table"))?;
+            Some(GlyphSet::from_table(&std::fs::read_to_string(path)?)?)
+        }
+        None if args.iter().any(|arg| arg == "--hex") => Some(GlyphSet::seven_segment_hex()),
+        None => None,
+    };
+    if let Some(glyphs) = glyphs {
+        println!("{:?}", decode_all(&input, &glyphs)?);
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(line: &str) -> Decoding {
+        decode_with(&GlyphSet::seven_segment(), line)
+    }
+
+    fn decode_with(glyphs: &GlyphSet, line: &str) -> Decoding {
+        let (signal, outputs) = parse_line(line).unwrap();
+        decode_entry(glyphs, &signal, &outputs)
+    }
+
+    #[test]
+    fn decodes_full_entries() {
+        assert_eq!(
+            decode("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf"),
+            Decoding::Value(5353)
+        );
+    }
+
+    #[test]
+    fn decodes_missing_patterns_when_unambiguous() {
+        // Only the patterns for 1, 4, 7 and 8 plus the outputs, which still pin down the wiring
+        assert_eq!(
+            decode("ab eafb dab acedgfb | cdfeb fcadb cdfeb cdbaf"),
+            Decoding::Value(5353)
+        );
+    }
+
+    #[test]
+    fn reports_ambiguous_and_inconsistent_entries() {
+        // A lone two wire pattern can be wired either way around but always reads as 1
+        assert!(matches!(decode("ab | ab"), Decoding::Value(1)));
+        assert!(matches!(decode("abc | ab abc"), Decoding::Value(17)));
+        assert!(matches!(decode("abcde | abcde"), Decoding::Ambiguous(_)));
+        assert_eq!(decode("ab abc abd | ab"), Decoding::Inconsistent);
+        assert_eq!(decode("ab | xy"), Decoding::Inconsistent);
+    }
+
+    #[test]
+    fn decodes_any_number_of_digits() {
+        let signal = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab";
+        let line = format!("{} | {}", signal, ["cdfeb"; 19].join(" "));
+        assert_eq!(decode(&line), Decoding::Value(5_555_555_555_555_555_555));
+        assert_eq!(
+            decode_line(&GlyphSet::seven_segment(), &line).unwrap(),
+            Decoding::Value(5_555_555_555_555_555_555)
+        );
+
+        let line = format!("{} | {}", signal, ["cdfeb"; 20].join(" "));
+        assert_eq!(decode(&line), Decoding::Overflow);
+        assert_eq!(decode(&format!("{} |", signal)), Decoding::Value(0));
+    }
+
+    #[test]
+    fn decodes_hexadecimal_glyphs() {
+        let glyphs = GlyphSet::seven_segment_hex();
+        assert_eq!(glyphs.radix(), 16);
+        let signal = glyphs::DIGIT_SEGMENTS
+            .iter()
+            .chain(&glyphs::HEX_SEGMENTS)
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(
+            decode_with(&glyphs, &format!("{} | abde cf abdeg", signal)),
+            Decoding::Value(0xF1E)
+        );
+        let full_width = format!("{} | {}", signal, ["abde"; 16].join(" "));
+        assert_eq!(
+            decode_with(&glyphs, &full_width),
+            Decoding::Value(0xFFFF_FFFF_FFFF_FFFF)
+        );
+        let leading_zero = format!("{} | abcefg {}", signal, ["abde"; 16].join(" "));
+        assert_eq!(
+            decode_with(&glyphs, &leading_zero),
+            Decoding::Value(u64::MAX)
+        );
+        let too_wide = format!("{} | cf {}", signal, ["abde"; 16].join(" "));
+        assert_eq!(decode_with(&glyphs, &too_wide), Decoding::Overflow);
+
+        // Without the signal patterns the outputs alone fit more than one wiring
+        assert!(matches!(
+            decode_with(&glyphs, "| abde cf abdeg"),
+            Decoding::Ambiguous(_)
+        ));
+    }
+
+    #[test]
+    fn decodes_custom_glyph_tables() {
+        // A fourteen segment display only showing 0 to 3
+        let glyphs = GlyphSet::from_table(
+            "0 abcdefjn
+             1 bcj
+             2 abdegk
+             3 abcdgk",
+        )
+        .unwrap();
+        assert_eq!(glyphs.segment_count(), 14);
+        assert_eq!(glyphs.radix(), 4);
+        assert_eq!(
+            decode_with(&glyphs, "abcdefjn bcj abdegk abcdgk | abcdgk bcj abdegk"),
+            Decoding::Value(3 * 16 + 4 + 2)
+        );
+
+        assert!(GlyphSet::from_table("0 ab\n2 cd").is_err());
+        assert!(GlyphSet::from_table("0 ab\n1 ba").is_err());
+    }
+
+    #[test]
+    fn bitmasks_match_set_based_decoding() {
+        let glyphs = GlyphSet::seven_segment();
+        let lines =
+            read_lines(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/input.txt")).unwrap();
+        let partial = [
+            "ab eafb dab acedgfb | cdfeb fcadb cdfeb cdbaf",
+            "ab | ab",
+            "abc | ab abc",
+            "abcde | abcde",
+            "ab abc abd | ab",
+            "ab | xy",
+            "gcbe | cgeb gc",
+            "ab eafb dab acedgfb | cdfeb fcadb cdfeb cdbaf cdfeb fcadb cdfeb cdbaf cdfeb fcadb \
+             cdfeb cdbaf cdfeb fcadb cdfeb cdbaf cdfeb fcadb cdfeb cdbaf",
+        ];
+        for line in lines.iter().map(String::as_str).chain(partial) {
+            let (signal, outputs) = parse_line(line).unwrap();
+            let expected = set_based::decode_entry(&signal, &outputs);
+            assert_eq!(
+                decode_entry(&glyphs, &signal, &outputs),
+                expected,
+                "{}",
+                line
+            );
+            assert_eq!(decode_line(&glyphs, line).unwrap(), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn lookup_table_covers_every_digit() {
+        let glyphs = GlyphSet::seven_segment();
+        assert!(glyphs.is_seven_segment_decimal());
+        assert!(!GlyphSet::seven_segment_hex().is_seven_segment_decimal());
+        assert_eq!((0..128).filter_map(|mask| glyphs.digit(mask)).count(), 10);
+        assert_eq!(glyphs.digit(parse_segments("cf", 7).unwrap()), Some(1));
+        assert_eq!(glyphs.digit(parse_segments("abcdefg", 7).unwrap()), Some(8));
+        assert_eq!(parse_segments("gfedcba", 7), Some(0b111_1111));
+        assert_eq!(parse_segments("ah", 7), None);
+    }
+}
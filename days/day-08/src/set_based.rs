@@ -0,0 +1,119 @@
+//! The original `HashSet` based wiring search, kept to cross-check the bitmask decoder
+
+use super::glyphs::DIGIT_SEGMENTS;
+use super::Decoding;
+use std::collections::{HashMap, HashSet};
+
+/// Finds every wiring of scrambled wires to segments that turns all patterns into digits by
+/// assigning one wire at a time, dropping partial wirings that can't become a digit
+struct WiringSolver {
+    digits: Vec<HashSet<char>>,
+    patterns: Vec<HashSet<char>>,
+    wires: Vec<char>,
+}
+
+impl WiringSolver {
+    fn new(patterns: &[&str]) -> Self {
+        let patterns: Vec<HashSet<char>> = patterns
+            .iter()
+            .map(|pattern| HashSet::from_iter(pattern.chars()))
+            .collect();
+        let mut wires: Vec<char> = patterns.iter().flatten().cloned().collect();
+        wires.sort_unstable();
+        wires.dedup();
+
+        Self {
+            digits: DIGIT_SEGMENTS
+                .iter()
+                .map(|segments| HashSet::from_iter(segments.chars()))
+                .collect(),
+            patterns,
+            wires,
+        }
+    }
+
+    fn digit(&self, pattern: &HashSet<char>, wiring: &HashMap<char, char>) -> Option<u64> {
+        let segments: HashSet<char> = pattern.iter().map(|wire| wiring[wire]).collect();
+        self.digits
+            .iter()
+            .position(|digit| *digit == segments)
+            .map(|digit| digit as u64)
+    }
+
+    fn could_be_digit(&self, pattern: &HashSet<char>, wiring: &HashMap<char, char>) -> bool {
+        let known: HashSet<char> = pattern
+            .iter()
+            .filter_map(|wire| wiring.get(wire).cloned())
+            .collect();
+        self.digits
+            .iter()
+            .any(|digit| digit.len() == pattern.len() && known.is_subset(digit))
+    }
+
+    fn search(&self, wiring: &mut HashMap<char, char>, found: &mut Vec<HashMap<char, char>>) {
+        let wire = match self.wires.get(wiring.len()) {
+            Some(wire) => *wire,
+            None => {
+                found.push(wiring.clone());
+                return;
+            }
+        };
+
+        for segment in 'a'..='g' {
+            if wiring.values().any(|used| *used == segment) {
+                continue;
+            }
+            wiring.insert(wire, segment);
+            if self
+                .patterns
+                .iter()
+                .all(|pattern| self.could_be_digit(pattern, wiring))
+            {
+                self.search(wiring, found);
+            }
+            wiring.remove(&wire);
+        }
+    }
+
+    fn wirings(&self) -> Vec<HashMap<char, char>> {
+        let mut found = vec![];
+        if self.wires.iter().all(|wire| ('a'..='g').contains(wire)) {
+            self.search(&mut HashMap::new(), &mut found);
+        }
+        found
+    }
+}
+
+/// Decodes the output of one display, which works with missing signal patterns as long as
+/// every fitting wiring reads the output the same way
+pub fn decode_entry(signal: &[&str], outputs: &[&str]) -> Decoding {
+    let patterns: Vec<&str> = signal.iter().chain(outputs).cloned().collect();
+    let solver = WiringSolver::new(&patterns);
+
+    let mut values = vec![];
+    'wirings: for wiring in solver.wirings() {
+        let mut value: u64 = 0;
+        for output in outputs {
+            let digit = match solver.digit(&HashSet::from_iter(output.chars()), &wiring) {
+                Some(digit) => digit,
+                None => continue 'wirings,
+            };
+            value = match value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add(digit))
+            {
+                Some(value) => value,
+                None => return Decoding::Overflow,
+            };
+        }
+        values.push(value);
+    }
+    values.sort_unstable();
+    values.dedup();
+
+    match values.as_slice() {
+        [] => Decoding::Inconsistent,
+        [value] => Decoding::Value(*value),
+        _ => Decoding::Ambiguous(values),
+    }
+}
@@ -1,57 +1,132 @@
+mod render;
+
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use geometry::{BoundingBox, Point};
+use serde::Serialize;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
-use std::sync::Mutex;
 
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-#[derive(Debug, Clone, Default)]
-struct BasinTree {
-    neighbors: [Option<Box<BasinTree>>; 4],
-}
-
-impl BasinTree {
-    fn from_grid(
-        start_x: usize,
-        start_y: usize,
-        grid: &[Vec<u32>],
-        visited: &Mutex<HashSet<(usize, usize)>>,
-    ) -> Option<Box<Self>> {
-        let current_value = grid
-            .get(start_y)
-            .map(|row| row.get(start_x))
-            .flatten()
-            .cloned();
-
-        let current_value = current_value?;
-        {
-            let mut visited = visited.try_lock().ok()?;
-            if current_value == 9 || visited.contains(&(start_x, start_y)) {
-                return None;
-            }
-            visited.insert((start_x, start_y));
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connectivity {
+    Four,
+    /// Diagonal cells are neighbors too
+    Eight,
+}
+
+/// How cells connect, for finding low points as well as filling basins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Options {
+    connectivity: Connectivity,
+    /// Edges of the map wrap around to the opposite side
+    toroidal: bool,
+    /// Cells this high or higher separate basins
+    wall_height: u32,
+    /// Regions of equal height count as a single low point, instead of not at all
+    plateaus: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            connectivity: Connectivity::Four,
+            toroidal: false,
+            wall_height: 9,
+            plateaus: false,
         }
+    }
+}
 
-        let neighbors = [
-            BasinTree::from_grid(start_x, start_y + 1, grid, visited),
-            BasinTree::from_grid(start_x + 1, start_y, grid, visited),
-            BasinTree::from_grid(start_x - 1, start_y, grid, visited),
-            BasinTree::from_grid(start_x, start_y - 1, grid, visited),
-        ];
+/// Heights stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Heightmap {
+    width: usize,
+    height: usize,
+    heights: Vec<u32>,
+    options: Options,
+}
+
+impl Heightmap {
+    fn from_lines(lines: &[String], options: Options) -> Result<Self> {
+        let rows: Vec<Vec<u32>> = lines
+            .iter()
+            .map(|line| parse_line(line))
+            .collect::<Result<_>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            anyhow::bail!("Row {} is not {} cells wide", row + 1, width);
+        }
 
-        Some(Box::new(Self { neighbors }))
+        Ok(Self {
+            width,
+            height: rows.len(),
+            heights: rows.into_iter().flatten().collect(),
+            options,
+        })
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
     }
 
-    fn size(&self) -> usize {
-        self.neighbors
+    fn get(&self, point: (usize, usize)) -> u32 {
+        self.heights[self.index(point)]
+    }
+
+    fn points(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    fn is_wall(&self, point: (usize, usize)) -> bool {
+        self.get(point) >= self.options.wall_height
+    }
+
+    /// Moves by `offset`, wrapping around on toroidal maps and `None` past the edge otherwise
+    fn offset(&self, (x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        if self.options.toroidal {
+            let wrap = |value: usize, delta: isize, size: usize| {
+                (value as isize + delta).rem_euclid(size as isize) as usize
+            };
+            Some((wrap(x, dx, self.width), wrap(y, dy, self.height)))
+        } else {
+            Some((
+                x.checked_add_signed(dx).filter(|x| *x < self.width)?,
+                y.checked_add_signed(dy).filter(|y| *y < self.height)?,
+            ))
+        }
+    }
+
+    /// Cells sharing an edge with `point`, `None` for edges on the border of the map
+    fn edges(&self, point: (usize, usize)) -> impl Iterator<Item = Option<(usize, usize)>> + '_ {
+        ORTHOGONAL
             .iter()
-            .map(|neighbor| neighbor.as_ref().map(|n| n.size()).unwrap_or_default())
-            .sum::<usize>()
-            + 1
+            .map(move |offset| self.offset(point, *offset))
     }
+
+    fn neighbors(&self, point: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let diagonal: &[_] = match self.options.connectivity {
+            Connectivity::Four => &[],
+            Connectivity::Eight => &DIAGONAL,
+        };
+        ORTHOGONAL
+            .iter()
+            .chain(diagonal)
+            .filter_map(move |offset| self.offset(point, *offset))
+    }
+}
+
+/// Basin of every cell in the map's order, numbered in the order of the low points they drain to
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Basins {
+    labels: Vec<Option<usize>>,
+    sizes: Vec<usize>,
 }
 
 fn read_lines<P>(filename: &P) -> Result<Vec<String>>
@@ -73,69 +148,250 @@ fn parse_line(line: &str) -> Result<Vec<u32>> {
     heights
 }
 
-fn get_neighbor_value(heights: &[Vec<u32>], x: usize, y: usize) -> u32 {
-    heights
-        .get(y)
-        .map(|row| row.get(x))
-        .flatten()
-        .cloned()
-        .unwrap_or(u32::MAX)
-}
-
-fn find_low_points(heights: &[Vec<u32>]) -> Vec<(usize, usize)> {
-    let points = heights.iter().enumerate().flat_map(|(y, row)| {
-        row.iter().enumerate().map(move |(x, column)| {
-            let top = get_neighbor_value(heights, x, y - 1);
-            let left = get_neighbor_value(heights, x - 1, y);
-            let right = get_neighbor_value(heights, x + 1, y);
-            let bottom = get_neighbor_value(heights, x, y + 1);
-
-            if [top, left, right, bottom].iter().all(|val| column < val) {
-                Some((x, y))
-            } else {
-                None
+/// First cell of every region of equal height whose surroundings are all higher, which has to
+/// be a single cell unless plateaus are allowed
+fn find_low_points(heightmap: &Heightmap) -> Vec<(usize, usize)> {
+    let mut seen = vec![false; heightmap.heights.len()];
+    let mut low_points = vec![];
+    let mut stack = vec![];
+
+    for point in heightmap.points() {
+        if seen[heightmap.index(point)] {
+            continue;
+        }
+        let height = heightmap.get(point);
+        let mut size = 0;
+        let mut lowest = true;
+        seen[heightmap.index(point)] = true;
+        stack.push(point);
+
+        while let Some(cell) = stack.pop() {
+            size += 1;
+            for neighbor in heightmap.neighbors(cell) {
+                let neighbor_height = heightmap.get(neighbor);
+                if neighbor_height < height {
+                    lowest = false;
+                } else if neighbor_height == height && !seen[heightmap.index(neighbor)] {
+                    seen[heightmap.index(neighbor)] = true;
+                    stack.push(neighbor);
+                }
             }
-        })
-    });
+        }
+
+        if lowest && !heightmap.is_wall(point) && (size == 1 || heightmap.options.plateaus) {
+            low_points.push(point);
+        }
+    }
+
+    low_points
+}
+
+/// Floods outwards from every low point with an explicit stack, so a basin can be as large as
+/// the map without running out of stack
+fn label_basins(heightmap: &Heightmap, low_points: &[(usize, usize)]) -> Basins {
+    let mut labels = vec![None; heightmap.heights.len()];
+    let mut sizes = vec![];
+    let mut stack = vec![];
+
+    for low_point in low_points {
+        let label = sizes.len();
+        let mut size = 0;
+        if labels[heightmap.index(*low_point)].is_none() {
+            labels[heightmap.index(*low_point)] = Some(label);
+            stack.push(*low_point);
+        }
+
+        while let Some(point) = stack.pop() {
+            size += 1;
+            for neighbor in heightmap.neighbors(point) {
+                let index = heightmap.index(neighbor);
+                if labels[index].is_none() && !heightmap.is_wall(neighbor) {
+                    labels[index] = Some(label);
+                    stack.push(neighbor);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+
+    Basins { labels, sizes }
+}
 
-    points.flatten().collect()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Wall,
+    /// Not a wall, but no low point drains it
+    Unclaimed,
+    Basin(usize),
+    /// Reachable from several low points, so it goes to the nearest one
+    Contested(usize),
 }
 
-fn part_one(lines: &[String]) -> Result<u32> {
-    let heights: Result<Vec<Vec<u32>>> = lines.iter().map(|line| parse_line(line)).collect();
-    let heights = heights?;
+impl Cell {
+    fn basin(&self) -> Option<usize> {
+        match self {
+            Cell::Basin(basin) | Cell::Contested(basin) => Some(*basin),
+            Cell::Wall | Cell::Unclaimed => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Basin {
+    low_point: Point<usize>,
+    area: usize,
+    /// Cell edges bordering on another basin, a wall or the edge of the map
+    perimeter: usize,
+    bounds: BoundingBox<usize>,
+    /// Whether the basin shares its region with other low points
+    contested: bool,
+}
+
+/// Every cell of the map assigned to the basin of a low point, one basin per low point
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segmentation {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    /// Basin cells with an edge on a cell outside their basin or the border of the map
+    boundary: Vec<bool>,
+    basins: Vec<Basin>,
+}
+
+impl Segmentation {
+    fn new(heightmap: &Heightmap) -> Self {
+        let low_points = find_low_points(heightmap);
+        let regions = label_basins(heightmap, &low_points).labels;
+        let mut cells: Vec<Cell> = regions
+            .iter()
+            .zip(heightmap.points())
+            .map(|(region, point)| match region {
+                Some(basin) => Cell::Basin(*basin),
+                None if heightmap.is_wall(point) => Cell::Wall,
+                None => Cell::Unclaimed,
+            })
+            .collect();
+
+        // A low point already flooded from an earlier one shares its region, which is split
+        // between all of them by distance instead
+        let mut sharing = vec![vec![]; low_points.len()];
+        for (basin, low_point) in low_points.iter().enumerate() {
+            if let Some(owner) = regions[heightmap.index(*low_point)].filter(|o| *o != basin) {
+                sharing[owner].push(basin);
+            }
+        }
+        for (owner, mut others) in sharing.into_iter().enumerate() {
+            if others.is_empty() {
+                continue;
+            }
+            others.insert(0, owner);
+            let mut queue: VecDeque<_> = others
+                .iter()
+                .map(|basin| (low_points[*basin], *basin))
+                .collect();
+            for (point, basin) in &queue {
+                cells[heightmap.index(*point)] = Cell::Contested(*basin);
+            }
+            while let Some((point, basin)) = queue.pop_front() {
+                for neighbor in heightmap.neighbors(point) {
+                    let index = heightmap.index(neighbor);
+                    if cells[index] == Cell::Basin(owner) {
+                        cells[index] = Cell::Contested(basin);
+                        queue.push_back((neighbor, basin));
+                    }
+                }
+            }
+        }
 
-    let low_points = find_low_points(&heights);
+        let mut segmentation = Self {
+            width: heightmap.width,
+            height: heightmap.height,
+            cells,
+            boundary: vec![false; heightmap.heights.len()],
+            basins: low_points
+                .iter()
+                .map(|(x, y)| {
+                    let low_point = Point::new(*x, *y);
+                    Basin {
+                        low_point,
+                        area: 0,
+                        perimeter: 0,
+                        bounds: BoundingBox::new(low_point, low_point),
+                        contested: false,
+                    }
+                })
+                .collect(),
+        };
+
+        for point in heightmap.points() {
+            let cell = segmentation.cell(point);
+            if let Some(id) = cell.basin() {
+                let borders = heightmap
+                    .edges(point)
+                    .filter(|edge| {
+                        edge.and_then(|other| segmentation.cell(other).basin()) != Some(id)
+                    })
+                    .count();
+                segmentation.boundary[heightmap.index(point)] = borders > 0;
+                let basin = &mut segmentation.basins[id];
+                basin.area += 1;
+                basin.perimeter += borders;
+                basin.bounds = basin
+                    .bounds
+                    .union(&BoundingBox::new(point.into(), point.into()));
+                basin.contested |= matches!(cell, Cell::Contested(_));
+            }
+        }
+
+        segmentation
+    }
+
+    fn cell(&self, (x, y): (usize, usize)) -> Cell {
+        self.cells[y * self.width + x]
+    }
+
+    fn points_where<F: Fn(&Cell) -> bool>(&self, predicate: F) -> Vec<Point<usize>> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| predicate(cell))
+            .map(|(index, _)| Point::new(index % self.width, index / self.width))
+            .collect()
+    }
+
+    fn unclaimed(&self) -> Vec<Point<usize>> {
+        self.points_where(|cell| *cell == Cell::Unclaimed)
+    }
+
+    /// Cells that could drain to more than one low point
+    fn contested(&self) -> Vec<Point<usize>> {
+        self.points_where(|cell| matches!(cell, Cell::Contested(_)))
+    }
+
+    fn is_boundary(&self, (x, y): (usize, usize)) -> bool {
+        self.boundary[y * self.width + x]
+    }
+}
+
+fn part_one(lines: &[String], options: Options) -> Result<u32> {
+    let heightmap = Heightmap::from_lines(lines, options)?;
+
+    let low_points = find_low_points(&heightmap);
 
     Ok(low_points
         .iter()
-        .map(|(x, y)| {
-            heights
-                .get(*y)
-                .map(|row| row.get(*x))
-                .flatten()
-                .cloned()
-                .unwrap()
-                + 1
-        })
+        .map(|point| heightmap.get(*point) + 1)
         .sum())
 }
 
-fn part_two(lines: &[String]) -> Result<usize> {
-    let heights: Result<Vec<Vec<u32>>> = lines.iter().map(|line| parse_line(line)).collect();
-    let heights = heights?;
-
-    let low_points = find_low_points(&heights);
+fn part_two(lines: &[String], options: Options) -> Result<usize> {
+    let heightmap = Heightmap::from_lines(lines, options)?;
 
-    let trees: Result<Vec<Box<BasinTree>>> = low_points
+    let mut sizes: Vec<usize> = Segmentation::new(&heightmap)
+        .basins
         .iter()
-        .map(|(x, y)| {
-            BasinTree::from_grid(*x, *y, &heights, &Mutex::new(HashSet::new()))
-                .ok_or_else(|| anyhow::anyhow!("Could not construct tree for basin"))
-        })
+        .map(|basin| basin.area)
         .collect();
-    let trees = trees?;
-    let mut sizes = trees.iter().map(|tree| tree.size()).collect::<Vec<usize>>();
     sizes.sort_unstable();
     let top_three = sizes.iter().rev().take(3);
     Ok(top_three.product())
@@ -147,9 +403,239 @@ fn main() -> Result<()> {
         .ok_or_else(|| anyhow::anyhow!("Couldn't get parent directory"))?
         .join(INPUT_FILE_PATH);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    let options = Options {
+        connectivity: if args.iter().any(|arg| arg == "--eight") {
+            Connectivity::Eight
+        } else {
+            Connectivity::Four
+        },
+        toroidal: args.iter().any(|arg| arg == "--toroidal"),
+        wall_height: match args.iter().position(|arg| arg == "--wall") {
+            Some(position) => args
+                .get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--wall needs a height"))?
+                .parse()
+                .context("Could not parse wall height")?,
+            None => Options::default().wall_height,
+        },
+        plateaus: args.iter().any(|arg| arg == "--plateaus"),
+    };
+
     let input = read_lines(&input_path)?;
-    println!("{:?}", part_one(&input)?);
-    println!("{:?}", part_two(&input)?);
+    println!("{:?}", part_one(&input, options)?);
+    println!("{:?}", part_two(&input, options)?);
+
+    let render_directory = args
+        .iter()
+        .position(|arg| arg == "--render")
+        .map(|position| {
+            args.get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--render needs an output directory"))
+        })
+        .transpose()?;
+    let ansi = args.iter().any(|arg| arg == "--ansi");
+    if render_directory.is_some() || ansi {
+        let heightmap = Heightmap::from_lines(&input, options)?;
+        let segmentation = Segmentation::new(&heightmap);
+        if ansi {
+            println!("{}", render::ansi(&heightmap, &segmentation));
+        }
+        if let Some(directory) = render_directory {
+            render::write_all(&heightmap, &segmentation, Path::new(directory))?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        [
+            "2199943210",
+            "3987894921",
+            "9856789892",
+            "8767896789",
+            "9899965678",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn solves_example() {
+        assert_eq!(part_one(&example(), Options::default()).unwrap(), 15);
+        assert_eq!(part_two(&example(), Options::default()).unwrap(), 1134);
+    }
+
+    #[test]
+    fn labels_every_basin_cell() {
+        let heightmap = Heightmap::from_lines(&example(), Options::default()).unwrap();
+        let basins = label_basins(&heightmap, &find_low_points(&heightmap));
+        assert_eq!(basins.sizes, vec![3, 9, 14, 9]);
+        assert_eq!(basins.labels[heightmap.index((0, 0))], Some(0));
+        assert_eq!(basins.labels[heightmap.index((9, 0))], Some(1));
+        assert_eq!(basins.labels[heightmap.index((2, 0))], None);
+        assert_eq!(
+            basins.labels.iter().flatten().count(),
+            basins.sizes.iter().sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn segments_example() {
+        let heightmap = Heightmap::from_lines(&example(), Options::default()).unwrap();
+        let segmentation = Segmentation::new(&heightmap);
+        let basin = &segmentation.basins[0];
+        assert_eq!(basin.low_point, Point::new(1, 0));
+        assert_eq!(basin.area, 3);
+        assert_eq!(basin.perimeter, 8);
+        assert_eq!(
+            basin.bounds,
+            BoundingBox::new(Point::new(0, 0), Point::new(1, 1))
+        );
+        assert_eq!(segmentation.cell((2, 0)), Cell::Wall);
+        assert!(segmentation.unclaimed().is_empty());
+        assert!(segmentation.contested().is_empty());
+        assert!(segmentation.is_boundary((0, 0)));
+        assert!(!segmentation.is_boundary((3, 2)));
+    }
+
+    #[test]
+    fn finds_unclaimed_and_contested_cells() {
+        // The left region has two low points, the right a plateau with none
+        let lines: Vec<String> = ["18519555", "88889555"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let heightmap = Heightmap::from_lines(&lines, Options::default()).unwrap();
+        let segmentation = Segmentation::new(&heightmap);
+
+        assert_eq!(segmentation.basins.len(), 2);
+        assert!(segmentation.basins.iter().all(|basin| basin.contested));
+        assert_eq!(segmentation.basins[0].area, 4);
+        assert_eq!(segmentation.basins[1].area, 4);
+        assert_eq!(segmentation.cell((1, 0)), Cell::Contested(0));
+        assert_eq!(segmentation.cell((2, 0)), Cell::Contested(1));
+        assert_eq!(segmentation.contested().len(), 8);
+        assert_eq!(segmentation.unclaimed().len(), 6);
+        assert_eq!(segmentation.cell((4, 1)), Cell::Wall);
+    }
+
+    #[test]
+    fn renders_example() {
+        let heightmap = Heightmap::from_lines(&example(), Options::default()).unwrap();
+        let segmentation = Segmentation::new(&heightmap);
+
+        let mut ppm = vec![];
+        render::write_ppm(&segmentation, &mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n10 5\n255\n"));
+        assert_eq!(ppm.len(), 12 + 10 * 5 * 3);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render::json(&segmentation).unwrap()).unwrap();
+        assert_eq!(json["basins"][2]["area"], 14);
+        assert_eq!(json["basins"][0]["low_point"]["x"], 1);
+        assert_eq!(json["labels"][0][0], 0);
+        assert!(json["labels"][0][2].is_null());
+
+        let ansi = render::ansi(&heightmap, &segmentation);
+        assert_eq!(ansi.lines().count(), 5);
+        assert!(ansi.contains("\x1b[48;2;"));
+    }
+
+    #[test]
+    fn floods_large_basins_without_recursion() {
+        let mut lines = vec!["8".repeat(1000); 1000];
+        lines[0].replace_range(0..1, "0");
+        assert_eq!(part_two(&lines, Options::default()).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn rejects_ragged_maps() {
+        assert!(
+            Heightmap::from_lines(&["123".to_string(), "12".to_string()], Options::default())
+                .is_err()
+        );
+    }
+
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_plateau_minima() {
+        let lines = lines(&["5553", "5553", "9999", "2332"]);
+        let options = Options {
+            plateaus: true,
+            ..Options::default()
+        };
+        let heightmap = Heightmap::from_lines(&lines, options).unwrap();
+        assert_eq!(find_low_points(&heightmap), vec![(3, 0), (0, 3), (3, 3)]);
+        assert_eq!(part_one(&lines, Options::default()).unwrap(), 3 + 3);
+
+        // Without plateaus the top two rows are left without a low point
+        let segmentation =
+            Segmentation::new(&Heightmap::from_lines(&lines, Options::default()).unwrap());
+        assert_eq!(segmentation.unclaimed().len(), 8);
+        let segmentation = Segmentation::new(&heightmap);
+        assert_eq!(segmentation.basins[0].area, 8);
+        assert_eq!(segmentation.cell((1, 3)), Cell::Contested(1));
+    }
+
+    #[test]
+    fn connects_diagonals() {
+        let lines = lines(&["19", "91"]);
+        assert_eq!(part_two(&lines, Options::default()).unwrap(), 1);
+        let options = Options {
+            connectivity: Connectivity::Eight,
+            ..Options::default()
+        };
+        // Diagonally the two ones form a plateau
+        assert_eq!(part_one(&lines, options).unwrap(), 0);
+        let options = Options {
+            plateaus: true,
+            ..options
+        };
+        let heightmap = Heightmap::from_lines(&lines, options).unwrap();
+        assert_eq!(find_low_points(&heightmap), vec![(0, 0)]);
+        let segmentation = Segmentation::new(&heightmap);
+        assert_eq!(segmentation.basins[0].area, 2);
+        assert_eq!(segmentation.basins[0].perimeter, 8);
+    }
+
+    #[test]
+    fn wraps_around_toroidal_maps() {
+        let lines = lines(&["0994", "9999", "8999"]);
+        let options = Options {
+            toroidal: true,
+            ..Options::default()
+        };
+        // The 4 and 8 are next to the 0 across the edges, so they stop being low points
+        assert_eq!(part_one(&lines, Options::default()).unwrap(), 1 + 5 + 9);
+        assert_eq!(part_one(&lines, options).unwrap(), 1);
+        let segmentation = Segmentation::new(&Heightmap::from_lines(&lines, options).unwrap());
+        assert_eq!(segmentation.basins[0].area, 3);
+        assert_eq!(segmentation.basins[0].perimeter, 8);
+    }
+
+    #[test]
+    fn uses_configurable_wall_height() {
+        let options = Options {
+            wall_height: 7,
+            ..Options::default()
+        };
+        let mut sizes: Vec<usize> =
+            Segmentation::new(&Heightmap::from_lines(&example(), options).unwrap())
+                .basins
+                .iter()
+                .map(|basin| basin.area)
+                .collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![3, 3, 4, 9]);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Cell, Heightmap, Segmentation};
+use anyhow::{Context, Result};
+use geometry::Point;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+type Rgb = [u8; 3];
+
+const WALL_COLOR: Rgb = [0, 0, 0];
+const UNCLAIMED_COLOR: Rgb = [128, 128, 128];
+
+/// Spreads basin colors around the hue circle by the golden angle, so neighboring ids differ
+fn basin_color(basin: usize) -> Rgb {
+    let hue = (basin as f64 * 137.507_764) % 360.0;
+    let sector = hue / 60.0;
+    let rising = (255.0 * (1.0 - (sector % 2.0 - 1.0).abs())) as u8;
+    match sector as u32 {
+        0 => [255, rising, 0],
+        1 => [rising, 255, 0],
+        2 => [0, 255, rising],
+        3 => [0, rising, 255],
+        4 => [rising, 0, 255],
+        _ => [255, 0, rising],
+    }
+}
+
+/// Basins in their own color, darker along their boundary and striped where contested
+fn cell_color(segmentation: &Segmentation, (x, y): (usize, usize)) -> Rgb {
+    let cell = segmentation.cell((x, y));
+    let color = match cell {
+        Cell::Wall => return WALL_COLOR,
+        Cell::Unclaimed => return UNCLAIMED_COLOR,
+        Cell::Basin(basin) | Cell::Contested(basin) => basin_color(basin),
+    };
+    let darken =
+        segmentation.is_boundary((x, y)) || matches!(cell, Cell::Contested(_)) && (x + y) % 2 == 0;
+    if darken {
+        color.map(|channel| channel / 2)
+    } else {
+        color
+    }
+}
+
+pub fn write_ppm<W: Write>(segmentation: &Segmentation, mut writer: W) -> Result<()> {
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        segmentation.width, segmentation.height
+    )?;
+    for y in 0..segmentation.height {
+        for x in 0..segmentation.width {
+            writer.write_all(&cell_color(segmentation, (x, y)))?;
+        }
+    }
+    Ok(())
+}
+
+/// The heights on a background of their basin's color, for 24-bit color terminals
+pub fn ansi(heightmap: &Heightmap, segmentation: &Segmentation) -> String {
+    (0..heightmap.height)
+        .map(|y| {
+            let mut row = String::new();
+            for x in 0..heightmap.width {
+                let [red, green, blue] = cell_color(segmentation, (x, y));
+                row += &format!(
+                    "\x1b[48;2;{};{};{}m{}",
+                    red,
+                    green,
+                    blue,
+                    heightmap.get((x, y))
+                );
+            }
+            row + "\x1b[0m"
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    width: usize,
+    height: usize,
+    basins: &'a [crate::Basin],
+    /// Basin of every cell by row, `null` for walls and unclaimed cells
+    labels: Vec<Vec<Option<usize>>>,
+    unclaimed: Vec<Point<usize>>,
+    contested: Vec<Point<usize>>,
+}
+
+pub fn json(segmentation: &Segmentation) -> Result<String> {
+    let labels = segmentation
+        .cells
+        .chunks(segmentation.width.max(1))
+        .map(|row| row.iter().map(Cell::basin).collect())
+        .collect();
+    let report = Report {
+        width: segmentation.width,
+        height: segmentation.height,
+        basins: &segmentation.basins,
+        labels,
+        unclaimed: segmentation.unclaimed(),
+        contested: segmentation.contested(),
+    };
+    serde_json::to_string_pretty(&report).context("Could not serialize basins")
+}
+
+/// Writes `basins.ppm`, `basins.ans` and `basins.json` into `directory`
+pub fn write_all(
+    heightmap: &Heightmap,
+    segmentation: &Segmentation,
+    directory: &Path,
+) -> Result<()> {
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Could not create {}", directory.display()))?;
+
+    write_ppm(
+        segmentation,
+        BufWriter::new(File::create(directory.join("basins.ppm"))?),
+    )?;
+    std::fs::write(directory.join("basins.ans"), ansi(heightmap, segmentation))?;
+    std::fs::write(directory.join("basins.json"), json(segmentation)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use crate::language::{BracketLanguage, Scan, Scanner};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Closes a different chunk than the innermost open one
+    Mismatched {
+        expected: String,
+        opener: String,
+        opener_column: usize,
+    },
+    /// Closes a chunk while none are open
+    Unmatched,
+    /// Isn't part of the language at all
+    Unknown,
+}
+
+/// Where and why a line broke, with 1-based line and column numbers counted in characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub found: String,
+    pub problem: Problem,
+}
+
+fn column(source: &str, offset: usize) -> usize {
+    source[..offset].chars().count() + 1
+}
+
+impl Diagnostic {
+    /// The diagnostic for a corrupted or invalid scan of `source`, `None` for any other scan
+    pub fn from_scan(
+        language: &BracketLanguage,
+        line: usize,
+        source: &str,
+        scan: &Scan,
+    ) -> Option<Self> {
+        match scan {
+            Scan::Corrupted {
+                expected,
+                found,
+                offset,
+            } => Some(Self {
+                line,
+                column: column(source, *offset),
+                found: language.pair(*found).close.clone(),
+                problem: match expected {
+                    Some(pair) => Problem::Mismatched {
+                        expected: language.pair(*pair).close.clone(),
+                        opener: language.pair(*pair).open.clone(),
+                        opener_column: column(
+                            source,
+                            Scanner::new(language).opener_offset(source.as_bytes())?,
+                        ),
+                    },
+                    None => Problem::Unmatched,
+                },
+            }),
+            Scan::Invalid { character, offset } => Some(Self {
+                line,
+                column: column(source, *offset),
+                found: character.to_string(),
+                problem: Problem::Unknown,
+            }),
+            Scan::Complete | Scan::Incomplete => None,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match &self.problem {
+            Problem::Mismatched { expected, .. } => {
+                format!("expected `{}`, found `{}`", expected, self.found)
+            }
+            Problem::Unmatched => format!("unexpected closing delimiter `{}`", self.found),
+            Problem::Unknown => format!("unknown character `{}`", self.found),
+        }
+    }
+
+    /// Renders the diagnostic like rustc does, underlining the offending closer and the opener
+    /// it should have matched in `source`, the text of the line
+    pub fn render(&self, origin: &str, source: &str) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let mut lines = vec![
+            format!("error: {}", self.message()),
+            format!("{}--> {}:{}:{}", gutter, origin, self.line, self.column),
+            format!("{} |", gutter),
+            format!("{} | {}", number, source),
+        ];
+
+        let carets = "^".repeat(self.found.chars().count());
+        match &self.problem {
+            Problem::Mismatched {
+                opener,
+                opener_column,
+                ..
+            } => {
+                let indent = " ".repeat(opener_column - 1);
+                let width = opener.chars().count();
+                let gap = " ".repeat((self.column - opener_column).saturating_sub(width));
+                lines.push(format!(
+                    "{} | {}{}{}{} mismatched closing delimiter",
+                    gutter,
+                    indent,
+                    "-".repeat(width),
+                    gap,
+                    carets
+                ));
+                lines.push(format!("{} | {}|", gutter, indent));
+                lines.push(format!("{} | {}unclosed delimiter", gutter, indent));
+            }
+            Problem::Unmatched | Problem::Unknown => {
+                let label = if self.problem == Problem::Unmatched {
+                    "no chunk is open"
+                } else {
+                    "not a delimiter"
+                };
+                lines.push(format!(
+                    "{} | {}{} {}",
+                    gutter,
+                    " ".repeat(self.column - 1),
+                    carets,
+                    label
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,369 @@
+use anyhow::Result;
+use std::io::BufRead;
+
+/// What to do with text that isn't a delimiter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unknown {
+    Reject,
+    Ignore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pair {
+    pub open: String,
+    pub close: String,
+}
+
+/// Outcome of scanning a line, with pairs given by their index in the language and positions
+/// as byte offsets into the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scan {
+    /// Every chunk is closed
+    Complete,
+    /// Chunks are left open at the end of the line, see [`Scanner::closers`]
+    Incomplete,
+    /// A closer that doesn't match the innermost open chunk, `None` if nothing was open
+    Corrupted {
+        expected: Option<usize>,
+        found: usize,
+        offset: usize,
+    },
+    /// Text outside the language while unknown text is rejected
+    Invalid { character: char, offset: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Delimiter {
+    text: Vec<u8>,
+    pair: u8,
+    is_opener: bool,
+    /// Opens and closes the same pair, like `"`
+    symmetric: bool,
+}
+
+/// Whether a byte can be part of a word, so keywords like `end` don't match inside `append`
+fn is_word(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+impl Delimiter {
+    /// Whether the delimiter starts at `offset`, only matching a whole word at a word edge
+    fn matches_at(&self, line: &[u8], offset: usize) -> bool {
+        let end = offset + self.text.len();
+        line[offset..].starts_with(&self.text)
+            && !(is_word(self.text[0]) && offset > 0 && is_word(line[offset - 1]))
+            && !(is_word(self.text[self.text.len() - 1])
+                && line.get(end).is_some_and(|byte| is_word(*byte)))
+    }
+}
+
+/// What a byte can start, so the common single byte delimiters skip the search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lookup {
+    Unknown,
+    Delimiter {
+        pair: u8,
+        is_opener: bool,
+        symmetric: bool,
+    },
+    /// A longer delimiter, a word or the escape could start here
+    Search,
+}
+
+/// Chunks opened and closed by pairs of delimiters, which can be longer than one character.
+/// Delimiters starting or ending with a letter, digit or `_` only match at that end of a word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketLanguage {
+    pairs: Vec<Pair>,
+    /// Delimiters by their first byte, longest first so `/*` wins over `/`
+    delimiters: Vec<Vec<Delimiter>>,
+    lookup: Vec<Lookup>,
+    /// Character that makes the one after it plain text
+    escape: Option<char>,
+    unknown: Unknown,
+}
+
+impl BracketLanguage {
+    pub fn new<S: AsRef<str>>(pairs: &[(S, S)]) -> Result<Self> {
+        let pairs: Vec<Pair> = pairs
+            .iter()
+            .map(|(open, close)| Pair {
+                open: open.as_ref().to_string(),
+                close: close.as_ref().to_string(),
+            })
+            .collect();
+        if pairs.is_empty() || pairs.len() > u8::MAX as usize {
+            anyhow::bail!("A bracket language needs between 1 and {} pairs", u8::MAX);
+        }
+
+        let mut delimiters: Vec<Vec<Delimiter>> = vec![vec![]; 256];
+        for (index, pair) in pairs.iter().enumerate() {
+            if pair.open.is_empty() || pair.close.is_empty() {
+                anyhow::bail!("Delimiters of pair {} can't be empty", index + 1);
+            }
+            for (text, is_opener) in [(&pair.open, true), (&pair.close, false)] {
+                // A pair like `"`/`"` opens or closes depending on what's open
+                if is_opener || pair.open != pair.close {
+                    let candidates = &mut delimiters[text.as_bytes()[0] as usize];
+                    if candidates.iter().any(|other| other.text == text.as_bytes()) {
+                        anyhow::bail!("Delimiter {:?} is used twice", text);
+                    }
+                    candidates.push(Delimiter {
+                        text: text.as_bytes().to_vec(),
+                        pair: index as u8,
+                        is_opener,
+                        symmetric: pair.open == pair.close,
+                    });
+                }
+            }
+        }
+        for candidates in &mut delimiters {
+            candidates.sort_by_key(|delimiter| std::cmp::Reverse(delimiter.text.len()));
+        }
+
+        let mut language = Self {
+            pairs,
+            delimiters,
+            lookup: vec![],
+            escape: None,
+            unknown: Unknown::Reject,
+        };
+        language.build_lookup();
+        Ok(language)
+    }
+
+    fn build_lookup(&mut self) {
+        let mut escape_buffer = [0; 4];
+        let escape_start = self
+            .escape
+            .map(|escape| escape.encode_utf8(&mut escape_buffer).as_bytes()[0]);
+        self.lookup = (0..=u8::MAX)
+            .map(|byte| match self.delimiters[byte as usize].as_slice() {
+                _ if Some(byte) == escape_start => Lookup::Search,
+                [] => Lookup::Unknown,
+                [delimiter] if delimiter.text.len() == 1 && !is_word(byte) => Lookup::Delimiter {
+                    pair: delimiter.pair,
+                    is_opener: delimiter.is_opener,
+                    symmetric: delimiter.symmetric,
+                },
+                _ => Lookup::Search,
+            })
+            .collect();
+    }
+
+    /// The puzzle's `()`, `[]`, `{}` and `<>` with nothing else allowed
+    pub fn puzzle() -> Self {
+        Self::new(&[("(", ")"), ("[", "]"), ("{", "}"), ("<", ">")])
+            .expect("Puzzle pairs are distinct")
+    }
+
+    /// Reads one `<open> <close>` pair per line, plus optional `escape <char>` and
+    /// `ignore` lines, the latter skipping unknown text instead of rejecting it
+    pub fn from_config(config: &str) -> Result<Self> {
+        let mut pairs = vec![];
+        let mut escape = None;
+        let mut unknown = Unknown::Reject;
+        for line in config.lines().map(str::trim) {
+            match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [] => {}
+                ["ignore"] => unknown = Unknown::Ignore,
+                ["escape", character] => {
+                    let mut chars = character.chars();
+                    escape = match (chars.next(), chars.next()) {
+                        (Some(character), None) => Some(character),
+                        _ => anyhow::bail!("Escape {:?} is not a single character", character),
+                    };
+                }
+                [open, close] => pairs.push((*open, *close)),
+                _ => anyhow::bail!("Could not parse language line {:?}", line),
+            }
+        }
+
+        let mut language = Self::new(&pairs)?.with_unknown(unknown);
+        if let Some(escape) = escape {
+            language = language.with_escape(escape);
+        }
+        Ok(language)
+    }
+
+    pub fn with_escape(mut self, escape: char) -> Self {
+        self.escape = Some(escape);
+        self.build_lookup();
+        self
+    }
+
+    pub fn with_unknown(mut self, unknown: Unknown) -> Self {
+        self.unknown = unknown;
+        self
+    }
+
+    pub fn pair(&self, index: usize) -> &Pair {
+        &self.pairs[index]
+    }
+}
+
+/// Bytes taken by the UTF-8 character starting with `byte`
+fn char_width(byte: u8) -> usize {
+    match byte {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    }
+}
+
+fn char_at(line: &[u8], offset: usize) -> char {
+    let width = char_width(line[offset]);
+    let bytes = &line[offset..std::cmp::min(offset + width, line.len())];
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Scans lines of a language straight from their bytes, reusing one stack of open pairs so
+/// that scanning doesn't allocate once the stack is as deep as the deepest line
+pub struct Scanner<'a> {
+    language: &'a BracketLanguage,
+    /// Pairs of the open chunks, innermost last
+    stack: Vec<u8>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(language: &'a BracketLanguage) -> Self {
+        Self {
+            language,
+            stack: vec![],
+        }
+    }
+
+    /// Walks the line once. Validation and completion both only look at how this ends
+    pub fn scan(&mut self, line: &[u8]) -> Scan {
+        self.walk(line, |_, _| {})
+    }
+
+    /// Offset of the opener that the closer of a corrupted line should have matched. Only
+    /// diagnostics need it, so it's found by walking the line again while tracking openers
+    pub fn opener_offset(&mut self, line: &[u8]) -> Option<usize> {
+        let mut openers = vec![];
+        match self.walk(line, |depth, offset| {
+            openers.truncate(depth - 1);
+            openers.push(offset);
+        }) {
+            Scan::Corrupted {
+                expected: Some(_), ..
+            } => openers.get(self.stack.len() - 1).copied(),
+            _ => None,
+        }
+    }
+
+    /// Scans with `on_push` called with the depth and offset of every opener
+    fn walk<F: FnMut(usize, usize)>(&mut self, line: &[u8], mut on_push: F) -> Scan {
+        let language = self.language;
+        let mut escape_buffer = [0; 4];
+        let escape = language
+            .escape
+            .map(|escape| escape.encode_utf8(&mut escape_buffer).as_bytes());
+        self.stack.clear();
+        let mut offset = 0;
+
+        while let Some(byte) = line.get(offset) {
+            let (pair, is_opener, symmetric, width) = match language.lookup[*byte as usize] {
+                Lookup::Delimiter {
+                    pair,
+                    is_opener,
+                    symmetric,
+                } => (pair, is_opener, symmetric, 1),
+                lookup => {
+                    let rest = &line[offset..];
+                    if let Some(escape) = escape.filter(|escape| rest.starts_with(escape)) {
+                        offset += escape.len();
+                        offset += line.get(offset).map_or(0, |byte| char_width(*byte));
+                        continue;
+                    }
+                    let delimiter = match lookup {
+                        Lookup::Search => language.delimiters[*byte as usize]
+                            .iter()
+                            .find(|delimiter| delimiter.matches_at(line, offset)),
+                        _ => None,
+                    };
+                    match delimiter {
+                        Some(delimiter) => (
+                            delimiter.pair,
+                            delimiter.is_opener,
+                            delimiter.symmetric,
+                            delimiter.text.len(),
+                        ),
+                        None if language.unknown == Unknown::Reject => {
+                            return Scan::Invalid {
+                                character: char_at(line, offset),
+                                offset,
+                            }
+                        }
+                        None => {
+                            offset += 1;
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let closes_innermost = self.stack.last() == Some(&pair);
+            if is_opener && !(symmetric && closes_innermost) {
+                self.stack.push(pair);
+                on_push(self.stack.len(), offset);
+            } else if closes_innermost {
+                self.stack.pop();
+            } else {
+                return Scan::Corrupted {
+                    expected: self.stack.last().map(|pair| *pair as usize),
+                    found: pair as usize,
+                    offset,
+                };
+            }
+            offset += width;
+        }
+
+        if self.stack.is_empty() {
+            Scan::Complete
+        } else {
+            Scan::Incomplete
+        }
+    }
+
+    pub fn language(&self) -> &'a BracketLanguage {
+        self.language
+    }
+
+    /// Closers completing the last scanned line, borrowed from the language
+    pub fn closers(&self) -> impl Iterator<Item = &'a str> + '_ {
+        let language = self.language;
+        self.stack
+            .iter()
+            .rev()
+            .map(move |pair| language.pairs[*pair as usize].close.as_str())
+    }
+
+    /// Scans every line of `reader` through one reused buffer, calling `visit` with the line
+    /// number, the line without its line ending, the scan and the scanner
+    pub fn for_each_line<R, F>(&mut self, mut reader: R, mut visit: F) -> Result<()>
+    where
+        R: BufRead,
+        F: FnMut(usize, &[u8], Scan, &Self) -> Result<()>,
+    {
+        let mut buffer = vec![];
+        let mut number = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                return Ok(());
+            }
+            number += 1;
+            let mut line = buffer.as_slice();
+            line = line.strip_suffix(b"\n").unwrap_or(line);
+            line = line.strip_suffix(b"\r").unwrap_or(line);
+            let scan = self.scan(line);
+            visit(number, line, scan, self)?;
+        }
+    }
+}
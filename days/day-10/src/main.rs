@@ -1,5 +1,11 @@
+mod diagnostic;
+mod language;
+mod scoring;
+
 use anyhow::{Context, Result};
-use std::collections::{HashMap, VecDeque};
+use diagnostic::Diagnostic;
+use language::{BracketLanguage, Scan, Scanner};
+use scoring::{ConfigScoring, PuzzleScoring, ScoringScheme};
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
@@ -18,97 +24,114 @@ where
         .collect()
 }
 
-fn parse_line(line: &str) -> Vec<String> {
-    line.chars().map(|val| val.to_string()).collect()
+/// Diagnostics for every corrupted or invalid line, numbered from 1
+fn diagnose(lines: &[String], language: &BracketLanguage) -> Vec<Diagnostic> {
+    let mut scanner = Scanner::new(language);
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(number, line)| {
+            let scan = scanner.scan(line.as_bytes());
+            Diagnostic::from_scan(language, number + 1, line, &scan)
+        })
+        .collect()
 }
 
-fn validate_line(line: &[String]) -> Result<Vec<String>, String> {
-    let mut stack = VecDeque::new();
-    let expected_closings: HashMap<_, _> = [("{", "}"), ("(", ")"), ("[", "]"), ("<", ">")]
-        .into_iter()
-        .collect();
+/// Scores of every line seen so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Totals {
+    syntax_error_score: u64,
+    completion_scores: Vec<u128>,
+}
 
-    for character in line.iter() {
-        match character.as_str() {
-            "{" | "(" | "[" | "<" => stack.push_front(character),
-            "}" | ")" | "]" | ">" => {
-                let opening = stack.pop_front().ok_or_else(|| character.clone())?;
-                if expected_closings.get(opening.as_str()) != Some(&character.as_str()) {
-                    return Err(character.clone());
-                }
+impl Totals {
+    fn add(
+        &mut self,
+        scheme: &dyn ScoringScheme,
+        number: usize,
+        line: &[u8],
+        scan: Scan,
+        scanner: &Scanner,
+    ) -> Result<()> {
+        match scan {
+            Scan::Corrupted { found, .. } => {
+                let closer = &scanner.language().pair(found).close;
+                let points = scheme
+                    .syntax_error(closer)
+                    .ok_or_else(|| anyhow::anyhow!("No syntax error score for {:?}", closer))?;
+                self.syntax_error_score = self
+                    .syntax_error_score
+                    .checked_add(points)
+                    .ok_or_else(|| anyhow::anyhow!("Syntax error score overflowed"))?;
+            }
+            Scan::Incomplete => self
+                .completion_scores
+                .push(scheme.completion(&mut scanner.closers())?),
+            Scan::Complete => {}
+            Scan::Invalid { .. } => {
+                let source = String::from_utf8_lossy(line);
+                let diagnostic = Diagnostic::from_scan(scanner.language(), number, &source, &scan)
+                    .context("Invalid lines have a diagnostic")?;
+                anyhow::bail!(diagnostic.render("input", &source));
             }
-            _ => unreachable!(),
         }
+        Ok(())
     }
 
-    Ok(line.to_vec())
+    /// Median completion score, `None` without incomplete lines
+    fn middle_score(mut self) -> Option<u128> {
+        scoring::median(&mut self.completion_scores)
+    }
 }
 
-fn complete_line(line: &[String]) -> Vec<String> {
-    let mut stack = VecDeque::new();
-    let expected_closings: HashMap<_, _> = [("{", "}"), ("(", ")"), ("[", "]"), ("<", ">")]
-        .into_iter()
-        .collect();
-
-    for character in line.iter() {
-        match character.as_str() {
-            "{" | "(" | "[" | "<" => stack.push_front(character),
-            "}" | ")" | "]" | ">" => {
-                stack.pop_front();
-            }
-            _ => unreachable!(),
-        }
+fn totals(
+    lines: &[String],
+    language: &BracketLanguage,
+    scheme: &dyn ScoringScheme,
+) -> Result<Totals> {
+    let mut scanner = Scanner::new(language);
+    let mut totals = Totals::default();
+    for (number, line) in lines.iter().enumerate() {
+        let scan = scanner.scan(line.as_bytes());
+        totals.add(scheme, number + 1, line.as_bytes(), scan, &scanner)?;
     }
+    Ok(totals)
+}
 
-    stack
-        .iter()
-        .map(|opening| expected_closings[opening.as_str()].to_string())
-        .collect()
+/// Totals straight from a reader, for inputs too large to hold in memory
+fn stream_totals<R: BufRead>(
+    reader: R,
+    language: &BracketLanguage,
+    scheme: &dyn ScoringScheme,
+) -> Result<Totals> {
+    let mut totals = Totals::default();
+    Scanner::new(language).for_each_line(reader, |number, line, scan, scanner| {
+        totals.add(scheme, number, line, scan, scanner)
+    })?;
+    Ok(totals)
 }
 
-fn part_one(lines: &[String]) -> Result<u32> {
-    let errors = lines
-        .iter()
-        .map(|line| parse_line(line))
-        .map(|line| validate_line(&line))
-        .filter(|res| res.is_err())
-        .map(|res| res.unwrap_err());
-
-    let total_points = errors.fold(0, |acc, error| {
-        acc + match error.as_str() {
-            ")" => 3,
-            "]" => 57,
-            "}" => 1197,
-            ">" => 25137,
-            _ => unreachable!(),
-        }
-    });
-    Ok(total_points)
+fn part_one(
+    lines: &[String],
+    language: &BracketLanguage,
+    scheme: &dyn ScoringScheme,
+) -> Result<u64> {
+    Ok(totals(lines, language, scheme)?.syntax_error_score)
 }
 
-fn part_two(lines: &[String]) -> Result<i64> {
-    let incomplete_lines = lines
-        .iter()
-        .map(|line| parse_line(line))
-        .map(|line| validate_line(&line))
-        .filter(|res| res.is_ok());
-
-    let complete_lines = incomplete_lines.map(|line| complete_line(&line.expect("Expected line")));
-    let total_scores = complete_lines.map(|line| {
-        line.iter().fold(0_i64, |acc, expected_closing| {
-            let acc = acc * 5;
-            acc + match expected_closing.as_str() {
-                ")" => 1,
-                "]" => 2,
-                "}" => 3,
-                ">" => 4,
-                _ => unreachable!(),
-            }
-        })
-    });
-    let mut total_scores: Vec<_> = total_scores.collect();
-    total_scores.sort_unstable();
-    Ok(total_scores[total_scores.len() / 2])
+fn part_two(
+    lines: &[String],
+    language: &BracketLanguage,
+    scheme: &dyn ScoringScheme,
+) -> Result<Option<u128>> {
+    Ok(totals(lines, language, scheme)?.middle_score())
+}
+
+fn print_middle_score(score: Option<u128>) {
+    match score {
+        Some(score) => println!("{:?}", score),
+        None => println!("No incomplete lines"),
+    }
 }
 
 fn main() -> Result<()> {
@@ -117,9 +140,326 @@ fn main() -> Result<()> {
         .ok_or_else(|| anyhow::anyhow!("Couldn't get parent directory"))?
         .join(INPUT_FILE_PATH);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    let language = match args.iter().position(|arg| arg == "--language") {
+        Some(position) => {
+            let path = args
+                .get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--language needs a language file"))?;
+            BracketLanguage::from_config(&std::fs::read_to_string(path)?)?
+        }
+        None => BracketLanguage::puzzle(),
+    };
+    let scheme: Box<dyn ScoringScheme> = match args.iter().position(|arg| arg == "--scoring") {
+        Some(position) => Box::new(ConfigScoring::from_toml(
+            args.get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--scoring needs a scoring file"))?,
+        )?),
+        None => Box::new(PuzzleScoring),
+    };
+
+    if let Some(position) = args.iter().position(|arg| arg == "--stream") {
+        let path = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--stream needs a bracket file"))?;
+        let reader = io::BufReader::with_capacity(1 << 20, File::open(path)?);
+        let totals = stream_totals(reader, &language, scheme.as_ref())?;
+        println!("{:?}", totals.syntax_error_score);
+        print_middle_score(totals.middle_score());
+        return Ok(());
+    }
+
     let input = read_lines(&input_path)?;
-    println!("{:?}", part_one(&input)?);
-    println!("{:?}", part_two(&input)?);
+    println!("{:?}", part_one(&input, &language, scheme.as_ref())?);
+    print_middle_score(part_two(&input, &language, scheme.as_ref())?);
+
+    if args.iter().any(|arg| arg == "--diagnostics") {
+        let origin = input_path.display().to_string();
+        for diagnostic in diagnose(&input, &language) {
+            println!(
+                "{}\n",
+                diagnostic.render(&origin, &input[diagnostic.line - 1])
+            );
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diagnostic::Problem;
+    use language::Unknown;
+
+    fn scan(language: &BracketLanguage, line: &str) -> Scan {
+        Scanner::new(language).scan(line.as_bytes())
+    }
+
+    /// Closers of the chunks left open, `Err` with the scan if the line is corrupted or invalid
+    fn complete(language: &BracketLanguage, line: &str) -> Result<String, Scan> {
+        let mut scanner = Scanner::new(language);
+        match scanner.scan(line.as_bytes()) {
+            Scan::Complete | Scan::Incomplete => Ok(scanner.closers().collect()),
+            scan => Err(scan),
+        }
+    }
+
+    fn example() -> Vec<String> {
+        [
+            "[({(<(())[]>[[{[]{<()<>>",
+            "[(()[<>])]({[<{<<[]>>(",
+            "{([(<{}[<>[]}>{[]{[(<()>",
+            "(((({<>}<{<{<>}{[]{[]{}",
+            "[[<[([]))<([[{}[[()]]]",
+            "[{[{({}]{}}([{[{{{}}([]",
+            "{<[[]]>}<{[{[{[]{()[[[]",
+            "[<(<(<(<{}))><([]([]()",
+            "<{([([[(<>()){}]>(<<{{",
+            "<{([{{}}[<[[[<>{}]]]>[]]",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn solves_example() {
+        let language = BracketLanguage::puzzle();
+        assert_eq!(
+            part_one(&example(), &language, &PuzzleScoring).unwrap(),
+            26397
+        );
+        assert_eq!(
+            part_two(&example(), &language, &PuzzleScoring).unwrap(),
+            Some(288957)
+        );
+    }
+
+    #[test]
+    fn scans_puzzle_lines() {
+        let language = BracketLanguage::puzzle();
+        assert_eq!(scan(&language, "([]<>)"), Scan::Complete);
+        assert_eq!(
+            scan(&language, "(]"),
+            Scan::Corrupted {
+                expected: Some(0),
+                found: 1,
+                offset: 1
+            }
+        );
+        assert_eq!(
+            scan(&language, "())"),
+            Scan::Corrupted {
+                expected: None,
+                found: 0,
+                offset: 2
+            }
+        );
+        assert_eq!(
+            scan(&language, "(a)"),
+            Scan::Invalid {
+                character: 'a',
+                offset: 1
+            }
+        );
+        assert_eq!(
+            complete(&language, "[({(<(())[]>[[{[]{<()<>>").unwrap(),
+            "}}]])})]"
+        );
+    }
+
+    #[test]
+    fn matches_multi_character_delimiters() {
+        let language = BracketLanguage::new(&[("begin", "end"), ("/*", "*/"), ("(", ")")])
+            .unwrap()
+            .with_unknown(Unknown::Ignore);
+        assert_eq!(scan(&language, "begin x := (1) end"), Scan::Complete);
+        assert_eq!(complete(&language, "begin /* ( ").unwrap(), ")*/end");
+        assert_eq!(
+            scan(&language, "begin ( end"),
+            Scan::Corrupted {
+                expected: Some(2),
+                found: 0,
+                offset: 8
+            }
+        );
+    }
+
+    #[test]
+    fn matches_keywords_only_as_whole_words() {
+        let language = BracketLanguage::new(&[("begin", "end"), ("x", "y")])
+            .unwrap()
+            .with_unknown(Unknown::Ignore);
+        assert_eq!(scan(&language, "begin endless append end"), Scan::Complete);
+        assert_eq!(scan(&language, "begin begin_end end"), Scan::Complete);
+        assert_eq!(scan(&language, "begin(end)"), Scan::Complete);
+        assert_eq!(scan(&language, "begin endless"), Scan::Incomplete);
+        assert_eq!(scan(&language, "x max y"), Scan::Complete);
+
+        let strict = BracketLanguage::new(&[("begin", "end")]).unwrap();
+        assert_eq!(
+            scan(&strict, "beginend"),
+            Scan::Invalid {
+                character: 'b',
+                offset: 0
+            }
+        );
+    }
+
+    #[test]
+    fn applies_escape_and_ignore_rules() {
+        let language = BracketLanguage::from_config(
+            "( )
+             \" \"
+             escape \\
+             ignore",
+        )
+        .unwrap();
+        assert_eq!(scan(&language, r#"(say "hi \" :\)" )"#), Scan::Complete);
+        assert_eq!(complete(&language, r#"("("#).unwrap(), ")\")");
+        assert_eq!(
+            scan(&BracketLanguage::puzzle().with_escape('!'), "(!))"),
+            Scan::Complete
+        );
+        assert!(BracketLanguage::from_config("( )\n( ]").is_err());
+        assert!(BracketLanguage::from_config("(").is_err());
+    }
+
+    #[test]
+    fn diagnoses_corrupted_lines() {
+        let language = BracketLanguage::puzzle();
+        let diagnostics = diagnose(&example(), &language);
+        assert_eq!(diagnostics.len(), 5);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                line: 3,
+                column: 13,
+                found: "}".to_string(),
+                problem: Problem::Mismatched {
+                    expected: "]".to_string(),
+                    opener: "[".to_string(),
+                    opener_column: 8,
+                },
+            }
+        );
+        assert_eq!(
+            diagnostics[0].render("input.txt", &example()[2]),
+            "error: expected `]`, found `}`
+ --> input.txt:3:13
+  |
+3 | {([(<{}[<>[]}>{[]{[(<()>
+  |        -    ^ mismatched closing delimiter
+  |        |
+  |        unclosed delimiter"
+        );
+    }
+
+    #[test]
+    fn diagnoses_unmatched_closers_and_unknown_text() {
+        let language = BracketLanguage::new(&[("begin", "end")])
+            .unwrap()
+            .with_unknown(Unknown::Ignore);
+        let lines = vec!["begin end end".to_string()];
+        let diagnostic = &diagnose(&lines, &language)[0];
+        assert_eq!(diagnostic.problem, Problem::Unmatched);
+        assert_eq!(
+            diagnostic.render("code", &lines[0]),
+            "error: unexpected closing delimiter `end`
+ --> code:1:11
+  |
+1 | begin end end
+  |           ^^^ no chunk is open"
+        );
+
+        let lines = vec!["(<x>)".to_string()];
+        let diagnostic = &diagnose(&lines, &BracketLanguage::puzzle())[0];
+        assert_eq!(diagnostic.problem, Problem::Unknown);
+        assert_eq!(diagnostic.column, 3);
+        assert!(part_one(&lines, &BracketLanguage::puzzle(), &PuzzleScoring)
+            .unwrap_err()
+            .to_string()
+            .contains("^ not a delimiter"));
+    }
+
+    #[test]
+    fn streams_lines_from_a_reader() {
+        let language = BracketLanguage::puzzle();
+        let text = example().join("\r\n");
+        let streamed = stream_totals(text.as_bytes(), &language, &PuzzleScoring).unwrap();
+        assert_eq!(
+            streamed,
+            totals(&example(), &language, &PuzzleScoring).unwrap()
+        );
+        assert_eq!(streamed.syntax_error_score, 26397);
+        assert_eq!(streamed.middle_score(), Some(288957));
+
+        let error = stream_totals("()\n(x)\n".as_bytes(), &language, &PuzzleScoring).unwrap_err();
+        assert!(error.to_string().contains("input:2:2"));
+    }
+
+    #[test]
+    fn scores_with_a_config_scheme() {
+        let language = BracketLanguage::new(&[("begin", "end"), ("/*", "*/")])
+            .unwrap()
+            .with_unknown(Unknown::Ignore);
+        let scheme = ConfigScoring::from_toml_str(
+            r#"
+            completion_base = 10
+
+            [syntax_error]
+            end = 100
+            "*/" = 7
+
+            [completion]
+            end = 1
+            "*/" = 2
+            "#,
+        )
+        .unwrap();
+        let lines: Vec<String> = [
+            "begin /* end */",
+            "begin begin /*",
+            "/* */ */",
+            "begin /* x",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        assert_eq!(part_one(&lines, &language, &scheme).unwrap(), 107);
+        // Completions `*/ end end` and `*/ end` score 211 and 21
+        assert_eq!(part_two(&lines, &language, &scheme).unwrap(), Some(116));
+        assert!(part_one(&lines, &language, &PuzzleScoring).is_err());
+    }
+
+    #[test]
+    fn takes_the_median_of_any_number_of_scores() {
+        assert_eq!(scoring::median(&mut []), None);
+        assert_eq!(scoring::median(&mut [7]), Some(7));
+        assert_eq!(scoring::median(&mut [9, 1, 4]), Some(4));
+        assert_eq!(scoring::median(&mut [9, 1, 4, 2]), Some(3));
+        assert_eq!(
+            scoring::median(&mut [u128::MAX, u128::MAX - 2]),
+            Some(u128::MAX - 1)
+        );
+        let lines = vec!["()".to_string(), "(]".to_string()];
+        assert_eq!(
+            part_two(&lines, &BracketLanguage::puzzle(), &PuzzleScoring).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn checks_completion_scores_for_overflow() {
+        // 55 open chunks still fit in a u128, 56 don't
+        let language = BracketLanguage::puzzle();
+        let fits = vec!["<".repeat(55)];
+        assert_eq!(
+            part_two(&fits, &language, &PuzzleScoring).unwrap(),
+            Some((0..55).fold(0, |score: u128, _| score * 5 + 4))
+        );
+        let overflows = vec!["<".repeat(56)];
+        assert!(part_two(&overflows, &language, &PuzzleScoring).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Points for corrupted lines and for the closers that complete incomplete ones
+pub trait ScoringScheme {
+    /// Points for the first illegal closer of a corrupted line, `None` if it isn't scored
+    fn syntax_error(&self, closer: &str) -> Option<u64>;
+
+    /// Value of one closer in a completion, `None` if it isn't scored
+    fn completion_value(&self, closer: &str) -> Option<u128>;
+
+    /// Each closer multiplies the score so far by this before adding its own value
+    fn completion_base(&self) -> u128 {
+        5
+    }
+
+    /// Score of a whole completion, `Err` if a closer isn't scored or the score overflows
+    fn completion(&self, closers: &mut dyn Iterator<Item = &str>) -> Result<u128> {
+        let mut score: u128 = 0;
+        for closer in closers {
+            let value = self
+                .completion_value(closer)
+                .ok_or_else(|| anyhow::anyhow!("No completion score for {:?}", closer))?;
+            score = score
+                .checked_mul(self.completion_base())
+                .and_then(|score| score.checked_add(value))
+                .ok_or_else(|| anyhow::anyhow!("Completion score overflowed"))?;
+        }
+        Ok(score)
+    }
+}
+
+/// The puzzle's points for `)`, `]`, `}` and `>`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PuzzleScoring;
+
+impl ScoringScheme for PuzzleScoring {
+    fn syntax_error(&self, closer: &str) -> Option<u64> {
+        match closer {
+            ")" => Some(3),
+            "]" => Some(57),
+            "}" => Some(1197),
+            ">" => Some(25137),
+            _ => None,
+        }
+    }
+
+    fn completion_value(&self, closer: &str) -> Option<u128> {
+        match closer {
+            ")" => Some(1),
+            "]" => Some(2),
+            "}" => Some(3),
+            ">" => Some(4),
+            _ => None,
+        }
+    }
+}
+
+/// Scores read from a TOML file, for languages with other delimiters. TOML integers are 64 bit,
+/// completions are still scored in 128 bits
+///
+/// ```toml
+/// completion_base = 10
+///
+/// [syntax_error]
+/// end = 100
+/// "*/" = 7
+///
+/// [completion]
+/// end = 1
+/// "*/" = 2
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ConfigScoring {
+    #[serde(default = "default_completion_base")]
+    completion_base: u64,
+    #[serde(default)]
+    syntax_error: HashMap<String, u64>,
+    #[serde(default)]
+    completion: HashMap<String, u64>,
+}
+
+fn default_completion_base() -> u64 {
+    PuzzleScoring.completion_base() as u64
+}
+
+impl ConfigScoring {
+    pub fn from_toml_str(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Could not parse scoring scheme")
+    }
+
+    pub fn from_toml<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path.as_ref().display()))?;
+        Self::from_toml_str(&contents)
+    }
+}
+
+impl ScoringScheme for ConfigScoring {
+    fn syntax_error(&self, closer: &str) -> Option<u64> {
+        self.syntax_error.get(closer).copied()
+    }
+
+    fn completion_value(&self, closer: &str) -> Option<u128> {
+        self.completion.get(closer).map(|value| *value as u128)
+    }
+
+    fn completion_base(&self) -> u128 {
+        self.completion_base as u128
+    }
+}
+
+/// Middle of the scores, the mean of the two middle ones rounded down for even counts and
+/// `None` without any scores
+pub fn median(scores: &mut [u128]) -> Option<u128> {
+    if scores.is_empty() {
+        return None;
+    }
+    let odd = scores.len() % 2 == 1;
+    let (lower, upper, _) = scores.select_nth_unstable(scores.len() / 2);
+    let upper = *upper;
+    if odd {
+        return Some(upper);
+    }
+    let lower = lower.iter().copied().max()?;
+    Some(lower + (upper - lower) / 2)
+}
@@ -1,25 +1,30 @@
+mod render;
+
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufRead};
 use std::path::Path;
+use std::time::Duration;
 
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-#[derive(Default, Clone)]
-struct OctoGraph {
-    adjacency: HashMap<(usize, usize), Vec<(usize, usize)>>,
-    value_map: HashMap<(usize, usize), u8>,
+/// Energy levels stored row by row
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct OctoGrid {
+    width: usize,
+    height: usize,
+    energy: Vec<u8>,
 }
 
-impl Display for OctoGraph {
+impl Display for OctoGrid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..10 {
-            let line: Vec<String> = (0..10).map(|x| self.value_at((x, y)).to_string()).collect();
+        for row in self.energy.chunks(self.width.max(1)) {
+            let line: Vec<String> = row.iter().map(|energy| energy.to_string()).collect();
             f.write_str(&format!("{}\n", &line.join(" ")))?
         }
 
@@ -27,43 +32,138 @@ impl Display for OctoGraph {
     }
 }
 
-impl OctoGraph {
-    fn add_node(&mut self, coords: (usize, usize), value: u8) {
-        self.value_map.entry(coords).or_insert(value);
+impl OctoGrid {
+    fn from_lines(lines: &[String]) -> Result<Self> {
+        let rows: Vec<Vec<u8>> = lines
+            .iter()
+            .map(|line| parse_line(line))
+            .collect::<Result<_>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            anyhow::bail!("Row {} is not {} octopuses wide", row + 1, width);
+        }
+
+        Ok(Self {
+            width,
+            height: rows.len(),
+            energy: rows.into_iter().flatten().collect(),
+        })
     }
 
-    fn add_edge(&mut self, coords: (usize, usize), neighbor_coords: (usize, usize)) {
-        let adjacency_list = self.adjacency.entry(coords).or_insert(vec![]);
-        adjacency_list.push(neighbor_coords);
+    fn coords(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
     }
 
-    fn neighbors(&self, coords: (usize, usize)) -> Vec<(usize, usize)> {
-        self.adjacency
-            .get(&coords)
-            .expect("Could not get neighbors for coords")
-            .clone()
+    /// Indices of the up to eight octopuses around `index` that lie inside the grid
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> {
+        let (x, y) = self.coords(index);
+        let (width, height) = (self.width, self.height);
+        (-1..=1_isize)
+            .flat_map(|dy| (-1..=1_isize).map(move |dx| (dx, dy)))
+            .filter(|offset| *offset != (0, 0))
+            .filter_map(move |(dx, dy)| {
+                let x = x.checked_add_signed(dx).filter(|x| *x < width)?;
+                let y = y.checked_add_signed(dy).filter(|y| *y < height)?;
+                Some(y * width + x)
+            })
     }
 
-    fn incr_value(&mut self, coords: (usize, usize)) -> u8 {
-        let val = self.value_map.entry(coords).or_default();
-        *val += 1;
-        *val
+    /// Raises every energy level by one and lets octopuses above 9 flash, which raises their
+    /// neighbors in turn. Flashes come in waves, so the first holds the octopuses that flashed
+    /// on their own and each later one those pushed over by the wave before it
+    fn step(&mut self) -> (Vec<usize>, usize) {
+        let mut flashed = vec![false; self.energy.len()];
+        let mut flashes = vec![];
+        let mut to_visit = VecDeque::new();
+        for (index, energy) in self.energy.iter_mut().enumerate() {
+            *energy += 1;
+            if *energy > 9 {
+                flashed[index] = true;
+                to_visit.push_back((index, 1));
+            }
+        }
+
+        let mut depth = 0;
+        while let Some((current, wave)) = to_visit.pop_front() {
+            flashes.push(current);
+            depth = wave;
+            for neighbor in self.neighbors(current) {
+                if flashed[neighbor] {
+                    continue;
+                }
+
+                self.energy[neighbor] += 1;
+                if self.energy[neighbor] > 9 {
+                    flashed[neighbor] = true;
+                    to_visit.push_back((neighbor, wave + 1));
+                }
+            }
+        }
+
+        for index in &flashes {
+            self.energy[*index] = 0;
+        }
+
+        (flashes, depth)
     }
 
-    fn value_at(&self, coords: (usize, usize)) -> u8 {
-        *self
-            .value_map
-            .get(&coords)
-            .expect("Couldn't get value at coords")
+    fn all_eq(&self) -> bool {
+        self.energy.windows(2).all(|pair| pair[0] == pair[1])
     }
+}
 
-    fn flash(&mut self, coords: (usize, usize)) {
-        let val = self.value_map.entry(coords).or_default();
-        *val = 0;
+/// What happened during one step of the simulation
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StepReport {
+    /// Steps are counted from 1
+    step: usize,
+    /// `(x, y)` of every octopus that flashed, in the order the cascade reached them
+    flashes: Vec<(usize, usize)>,
+    /// Waves the cascade took, 0 if nothing flashed
+    depth: usize,
+    /// Whether every octopus flashed at once
+    synchronized: bool,
+}
+
+impl StepReport {
+    fn flash_count(&self) -> usize {
+        self.flashes.len()
     }
+}
 
-    fn all_eq(&self) -> bool {
-        HashSet::<&u8>::from_iter(self.value_map.values()).len() == 1
+/// Steps a grid forever, yielding a report per step
+#[derive(Debug, Clone)]
+struct OctoSimulation {
+    grid: OctoGrid,
+    step: usize,
+}
+
+impl OctoSimulation {
+    fn new(grid: OctoGrid) -> Self {
+        Self { grid, step: 0 }
+    }
+
+    fn grid(&self) -> &OctoGrid {
+        &self.grid
+    }
+}
+
+impl Iterator for OctoSimulation {
+    type Item = StepReport;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (flashes, depth) = self.grid.step();
+        self.step += 1;
+
+        Some(StepReport {
+            step: self.step,
+            synchronized: flashes.len() == self.grid.energy.len(),
+            flashes: flashes
+                .into_iter()
+                .map(|index| self.grid.coords(index))
+                .collect(),
+            depth,
+        })
     }
 }
 
@@ -84,108 +184,151 @@ fn parse_line(line: &str) -> Result<Vec<u8>> {
         .collect()
 }
 
-fn item_exists_at(x: usize, y: usize, lines: &[Vec<u8>]) -> Option<u8> {
-    let item = lines.get(y).map(|line| line.get(x)).flatten();
-    item.cloned()
-}
-
-fn construct_graph(lines: &[Vec<u8>]) -> OctoGraph {
-    let mut graph = OctoGraph::default();
-    for (y, line) in lines.iter().enumerate() {
-        for (x, value) in line.iter().enumerate() {
-            let neighbor_coords = [
-                (x - 1, y - 1),
-                (x, y - 1),
-                (x + 1, y - 1),
-                (x - 1, y),
-                (x + 1, y),
-                (x - 1, y + 1),
-                (x, y + 1),
-                (x + 1, y + 1),
-            ];
-
-            for (neighbor_x, neighbor_y) in neighbor_coords.into_iter() {
-                if item_exists_at(neighbor_x, neighbor_y, lines).is_some() {
-                    graph.add_node((x, y), *value);
-                    graph.add_edge((x, y), (neighbor_x, neighbor_y));
-                }
-            }
-        }
-    }
+fn part_one(lines: &[String]) -> Result<usize> {
+    let simulation = OctoSimulation::new(OctoGrid::from_lines(lines)?);
 
-    graph
+    Ok(simulation
+        .take(100)
+        .map(|report| report.flash_count())
+        .sum())
 }
 
-fn part_one(lines: &[String]) -> Result<i64> {
-    let lines: Result<Vec<Vec<u8>>> = lines.iter().map(|line| parse_line(line)).collect();
-    let lines = lines?;
+/// Steps simulated before giving up on a search
+const DEFAULT_STEP_LIMIT: usize = 100_000;
+
+/// How a grid ends up repeating itself. States are the energy levels after a number of steps,
+/// starting with the input at step 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cycle {
+    /// First step whose state comes back
+    start: usize,
+    /// Steps until it does
+    length: usize,
+    flashes_per_cycle: usize,
+    /// First step where every octopus flashed, `None` if that never happens
+    first_synchronized: Option<usize>,
+}
 
-    let mut graph = construct_graph(&lines);
+/// Remembers the states of a simulation by their hash, so memory grows with the number of
+/// steps rather than steps times octopuses. Matching hashes are confirmed by replaying the
+/// simulation up to the earlier step
+struct CycleDetector {
+    initial: OctoGrid,
+    seen: HashMap<u64, Vec<usize>>,
+}
 
-    let mut total_flashes = 0;
-    for _ in 0..100 {
-        let mut flashed = HashSet::new();
-        for y in 0..10_usize {
-            for x in 0..10_usize {
-                let mut to_visit = VecDeque::new();
-                to_visit.push_front((x, y));
+impl CycleDetector {
+    fn new(grid: &OctoGrid) -> Self {
+        Self {
+            initial: grid.clone(),
+            seen: HashMap::from([(Self::hash(grid), vec![0])]),
+        }
+    }
 
-                while let Some((current_x, current_y)) = to_visit.pop_front() {
-                    if flashed.contains(&(current_x, current_y)) {
-                        continue;
-                    }
+    fn hash(grid: &OctoGrid) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        grid.energy.hash(&mut hasher);
+        hasher.finish()
+    }
 
-                    let new_val = graph.incr_value((current_x, current_y));
-                    if new_val > 9 {
-                        total_flashes += 1;
-                        graph.flash((current_x, current_y));
-                        flashed.insert((current_x, current_y));
-                        for neighbor in graph.neighbors((current_x, current_y)) {
-                            to_visit.push_back(neighbor);
-                        }
-                    }
-                }
+    /// Records the state after `step`, returning the earlier step it repeats if any
+    fn record(&mut self, grid: &OctoGrid, step: usize) -> Option<usize> {
+        let steps = self.seen.entry(Self::hash(grid)).or_default();
+        let repeated = steps.iter().copied().find(|earlier| {
+            let mut replay = self.initial.clone();
+            for _ in 0..*earlier {
+                replay.step();
             }
+            replay.energy == grid.energy
+        });
+        if repeated.is_none() {
+            steps.push(step);
         }
+        repeated
     }
-
-    Ok(total_flashes)
 }
 
-fn part_two(lines: &[String]) -> Result<i64> {
-    let lines: Result<Vec<Vec<u8>>> = lines.iter().map(|line| parse_line(line)).collect();
-    let lines = lines?;
+/// Steps until a state repeats, failing if none does within `limit` steps. Every state the
+/// grid can reach shows up before the first repeat, so that also settles whether it ever
+/// synchronizes
+fn find_cycle(grid: OctoGrid, limit: usize) -> Result<Cycle> {
+    let mut detector = CycleDetector::new(&grid);
+    let mut flashes = vec![0];
+    let mut first_synchronized = None;
+    let mut simulation = OctoSimulation::new(grid);
+    while let Some(report) = simulation.next() {
+        flashes.push(report.flash_count());
+        if report.synchronized {
+            first_synchronized.get_or_insert(report.step);
+        }
+        if let Some(start) = detector.record(simulation.grid(), report.step) {
+            return Ok(Cycle {
+                start,
+                length: report.step - start,
+                flashes_per_cycle: flashes[start + 1..].iter().sum(),
+                first_synchronized,
+            });
+        }
+        if report.step >= limit {
+            break;
+        }
+    }
 
-    let mut graph = construct_graph(&lines);
+    anyhow::bail!("No state repeated within {} steps", limit)
+}
 
-    let mut total_steps = 0;
-    while !graph.all_eq() {
-        total_steps += 1;
-        let mut flashed = HashSet::new();
-        for y in 0..10_usize {
-            for x in 0..10_usize {
-                let mut to_visit = VecDeque::new();
-                to_visit.push_front((x, y));
+/// First step matching `predicate`, failing once `limit` steps have passed without one
+fn first_step<P>(simulation: OctoSimulation, limit: usize, mut predicate: P) -> Result<usize>
+where
+    P: FnMut(&StepReport) -> bool,
+{
+    simulation
+        .take(limit)
+        .find(|report| predicate(report))
+        .map(|report| report.step)
+        .ok_or_else(|| anyhow::anyhow!("No matching step within {} steps", limit))
+}
 
-                while let Some((current_x, current_y)) = to_visit.pop_front() {
-                    if flashed.contains(&(current_x, current_y)) {
-                        continue;
-                    }
+fn part_two(lines: &[String], limit: usize) -> Result<usize> {
+    let grid = OctoGrid::from_lines(lines)?;
+    if grid.all_eq() {
+        return Ok(0);
+    }
 
-                    let new_val = graph.incr_value((current_x, current_y));
-                    if new_val > 9 {
-                        graph.flash((current_x, current_y));
-                        flashed.insert((current_x, current_y));
-                        for neighbor in graph.neighbors((current_x, current_y)) {
-                            to_visit.push_back(neighbor);
-                        }
-                    }
-                }
-            }
+    let mut detector = CycleDetector::new(&grid);
+    let mut simulation = OctoSimulation::new(grid);
+    while let Some(report) = simulation.next() {
+        if report.synchronized {
+            return Ok(report.step);
+        }
+        if let Some(start) = detector.record(simulation.grid(), report.step) {
+            anyhow::bail!(
+                "The octopuses never synchronize, they repeat {} steps from step {} on",
+                report.step - start,
+                start
+            );
+        }
+        if report.step >= limit {
+            break;
         }
     }
 
-    Ok(total_steps)
+    anyhow::bail!("The octopuses did not synchronize within {} steps", limit)
+}
+
+/// First step where more than `count` octopuses flash
+fn first_step_with_more_than(lines: &[String], count: usize, limit: usize) -> Result<usize> {
+    let grid = OctoGrid::from_lines(lines)?;
+    if count >= grid.energy.len() {
+        anyhow::bail!(
+            "More than {} flashes can't happen with {} octopuses",
+            count,
+            grid.energy.len()
+        );
+    }
+    let simulation = OctoSimulation::new(grid);
+
+    first_step(simulation, limit, |report| report.flash_count() > count)
 }
 
 fn main() -> Result<()> {
@@ -194,9 +337,208 @@ fn main() -> Result<()> {
         .ok_or_else(|| anyhow::anyhow!("Couldn't get parent directory"))?
         .join(INPUT_FILE_PATH);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    let limit = match args.iter().position(|arg| arg == "--limit") {
+        Some(position) => args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--limit needs a step count"))?
+            .parse()
+            .context("Could not parse step limit")?,
+        None => DEFAULT_STEP_LIMIT,
+    };
+
     let input = read_lines(&input_path)?;
     println!("{:?}", part_one(&input)?);
-    println!("{:?}", part_two(&input)?);
+    println!("{:?}", part_two(&input, limit)?);
+
+    if args.iter().any(|arg| arg == "--cycle") {
+        println!("{:?}", find_cycle(OctoGrid::from_lines(&input)?, limit)?);
+    }
+    let steps = match args.iter().position(|arg| arg == "--steps") {
+        Some(position) => args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--steps needs a step count"))?
+            .parse()
+            .context("Could not parse step count")?,
+        None => 100,
+    };
+    if args.iter().any(|arg| arg == "--animate") {
+        let delay = match args.iter().position(|arg| arg == "--delay") {
+            Some(position) => args
+                .get(position + 1)
+                .ok_or_else(|| anyhow::anyhow!("--delay needs milliseconds"))?
+                .parse()
+                .context("Could not parse frame delay")?,
+            None => 100,
+        };
+        let simulation = OctoSimulation::new(OctoGrid::from_lines(&input)?);
+        render::animate(
+            simulation,
+            steps,
+            Duration::from_millis(delay),
+            io::stdout().lock(),
+        )?;
+    }
+    if let Some(position) = args.iter().position(|arg| arg == "--frames") {
+        let directory = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--frames needs an output directory"))?;
+        let simulation = OctoSimulation::new(OctoGrid::from_lines(&input)?);
+        render::write_frames(simulation, steps, Path::new(directory))?;
+    }
+    if let Some(position) = args.iter().position(|arg| arg == "--more-than") {
+        let count = args
+            .get(position + 1)
+            .ok_or_else(|| anyhow::anyhow!("--more-than needs a flash count"))?
+            .parse()
+            .context("Could not parse flash count")?;
+        println!("{:?}", first_step_with_more_than(&input, count, limit)?);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    fn example() -> Vec<String> {
+        lines(&[
+            "5483143223",
+            "2745854711",
+            "5264556173",
+            "6141336146",
+            "6357385478",
+            "4167524645",
+            "2176841721",
+            "6882881134",
+            "4846848554",
+            "5283751526",
+        ])
+    }
+
+    #[test]
+    fn solves_example() {
+        assert_eq!(part_one(&example()).unwrap(), 1656);
+        assert_eq!(part_two(&example(), DEFAULT_STEP_LIMIT).unwrap(), 195);
+    }
+
+    #[test]
+    fn steps_rectangular_grids() {
+        let mut grid =
+            OctoGrid::from_lines(&lines(&["11111", "19991", "19191", "19991", "11111"])).unwrap();
+        assert_eq!(grid.step().0.len(), 9);
+        assert_eq!(
+            grid.to_string(),
+            "3 4 5 4 3\n4 0 0 0 4\n5 0 0 0 5\n4 0 0 0 4\n3 4 5 4 3\n"
+        );
+
+        let mut grid = OctoGrid::from_lines(&lines(&["9", "8", "1"])).unwrap();
+        assert_eq!((grid.width, grid.height), (1, 3));
+        assert_eq!(grid.step().0.len(), 2);
+        assert_eq!(grid.to_string(), "0\n0\n3\n");
+        assert!(OctoGrid::from_lines(&lines(&["12", "1"])).is_err());
+    }
+
+    #[test]
+    fn reports_cascades() {
+        let grid = OctoGrid::from_lines(&lines(&["11111", "19991", "19191", "19991", "11111"]));
+        let mut simulation = OctoSimulation::new(grid.unwrap());
+        let report = simulation.next().unwrap();
+        assert_eq!(report.step, 1);
+        assert_eq!(report.flash_count(), 9);
+        assert_eq!(report.depth, 2);
+        assert_eq!(
+            report.flashes[..8],
+            [
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+        assert_eq!(report.flashes[8], (2, 2));
+        assert!(!report.synchronized);
+        assert_eq!(simulation.next().unwrap().flash_count(), 0);
+    }
+
+    #[test]
+    fn answers_queries_from_reports() {
+        let reports: Vec<_> = OctoSimulation::new(OctoGrid::from_lines(&example()).unwrap())
+            .take(10)
+            .collect();
+        assert_eq!(
+            reports.iter().map(StepReport::flash_count).sum::<usize>(),
+            204
+        );
+        assert_eq!(
+            first_step_with_more_than(&example(), 50, DEFAULT_STEP_LIMIT).unwrap(),
+            87
+        );
+        assert_eq!(
+            first_step_with_more_than(&example(), 99, DEFAULT_STEP_LIMIT).unwrap(),
+            195
+        );
+        assert!(first_step_with_more_than(&example(), 100, DEFAULT_STEP_LIMIT).is_err());
+    }
+
+    #[test]
+    fn renders_flashes() {
+        let grid = OctoGrid::from_lines(&lines(&["191", "111"])).unwrap();
+        let mut simulation = OctoSimulation::new(grid);
+        let report = simulation.next().unwrap();
+
+        let ansi = render::ansi(simulation.grid(), Some(&report));
+        assert!(ansi.starts_with("step 1: 1 flashes, 1 waves\n"));
+        assert_eq!(ansi.matches("\x1b[48;2;255;244;160m0 ").count(), 1);
+        assert_eq!(ansi.lines().count(), 3);
+
+        let mut ppm = vec![];
+        render::write_ppm(simulation.grid(), Some(&report), &mut ppm).unwrap();
+        let header = b"P6\n24 16\n255\n";
+        assert!(ppm.starts_with(header));
+        assert_eq!(ppm.len(), header.len() + 24 * 16 * 3);
+        let pixel = |x: usize, y: usize| {
+            let start = header.len() + (y * 24 + x) * 3;
+            ppm[start..start + 3].to_vec()
+        };
+        assert_eq!(pixel(12, 3), [255, 244, 160]);
+        assert_ne!(pixel(3, 12), [255, 244, 160]);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let cycle = find_cycle(OctoGrid::from_lines(&example()).unwrap(), 1000).unwrap();
+        assert_eq!(
+            cycle,
+            Cycle {
+                start: 195,
+                length: 10,
+                flashes_per_cycle: 100,
+                first_synchronized: Some(195),
+            }
+        );
+        assert!(find_cycle(OctoGrid::from_lines(&example()).unwrap(), 200).is_err());
+
+        let never = lines(&["09"]);
+        let cycle = find_cycle(OctoGrid::from_lines(&never).unwrap(), 1000).unwrap();
+        assert_eq!((cycle.start, cycle.length), (0, 9));
+        assert_eq!(cycle.first_synchronized, None);
+        assert!(part_two(&never, 1000).is_err());
+    }
+
+    #[test]
+    fn stops_at_the_step_limit() {
+        assert!(part_two(&example(), 194).is_err());
+        assert_eq!(part_two(&example(), 195).unwrap(), 195);
+        assert!(first_step_with_more_than(&lines(&["09"]), 1, 50).is_err());
+    }
+}
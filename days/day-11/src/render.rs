@@ -0,0 +1,129 @@
+use crate::{OctoGrid, OctoSimulation, StepReport};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+type Rgb = [u8; 3];
+
+const FLASH_COLOR: Rgb = [255, 244, 160];
+/// Pixels per octopus side in frame dumps
+const CELL_SIZE: usize = 8;
+
+/// Deep blue when drained, brightening towards cyan as the energy builds up
+fn energy_color(energy: u8) -> Rgb {
+    let level = u32::from(energy.min(9));
+    [
+        (8 + level * 8) as u8,
+        (16 + level * 20) as u8,
+        (48 + level * 22) as u8,
+    ]
+}
+
+/// Which octopuses flashed, by grid index
+fn flash_mask(grid: &OctoGrid, report: Option<&StepReport>) -> Vec<bool> {
+    let mut mask = vec![false; grid.energy.len()];
+    for (x, y) in report.iter().flat_map(|report| &report.flashes) {
+        mask[y * grid.width + x] = true;
+    }
+    mask
+}
+
+/// The energy levels with this step's flashes highlighted, for 24-bit color terminals
+pub fn ansi(grid: &OctoGrid, report: Option<&StepReport>) -> String {
+    let flashed = flash_mask(grid, report);
+    let mut frame = match report {
+        Some(report) => format!(
+            "step {}: {} flashes, {} waves\n",
+            report.step,
+            report.flash_count(),
+            report.depth
+        ),
+        None => "step 0\n".to_string(),
+    };
+    for (y, row) in grid.energy.chunks(grid.width.max(1)).enumerate() {
+        for (x, energy) in row.iter().enumerate() {
+            let [red, green, blue] = if flashed[y * grid.width + x] {
+                FLASH_COLOR
+            } else {
+                energy_color(*energy)
+            };
+            frame += &format!("\x1b[48;2;{};{};{}m{} ", red, green, blue, energy);
+        }
+        frame += "\x1b[0m\n";
+    }
+    frame
+}
+
+/// Plays `steps` steps in the terminal, redrawing in place every `delay`
+pub fn animate<W: Write>(
+    mut simulation: OctoSimulation,
+    steps: usize,
+    delay: Duration,
+    mut writer: W,
+) -> Result<()> {
+    write!(writer, "\x1b[2J\x1b[H{}", ansi(simulation.grid(), None))?;
+    for _ in 0..steps {
+        writer.flush()?;
+        std::thread::sleep(delay);
+        let report = simulation.next().context("Simulation ended")?;
+        write!(writer, "\x1b[H{}", ansi(simulation.grid(), Some(&report)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_ppm<W: Write>(
+    grid: &OctoGrid,
+    report: Option<&StepReport>,
+    mut writer: W,
+) -> Result<()> {
+    let flashed = flash_mask(grid, report);
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        grid.width * CELL_SIZE,
+        grid.height * CELL_SIZE
+    )?;
+    for (y, row) in grid.energy.chunks(grid.width.max(1)).enumerate() {
+        let colors: Vec<Rgb> = row
+            .iter()
+            .enumerate()
+            .map(|(x, energy)| {
+                if flashed[y * grid.width + x] {
+                    FLASH_COLOR
+                } else {
+                    energy_color(*energy)
+                }
+            })
+            .collect();
+        for _ in 0..CELL_SIZE {
+            for color in &colors {
+                for _ in 0..CELL_SIZE {
+                    writer.write_all(color)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes `step-0000.ppm` for the starting grid and one numbered frame per step after it
+pub fn write_frames(mut simulation: OctoSimulation, steps: usize, directory: &Path) -> Result<()> {
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Could not create {}", directory.display()))?;
+    let mut report = None;
+    for step in 0..=steps {
+        if step > 0 {
+            report = simulation.next();
+        }
+        let path = directory.join(format!("step-{:04}.ppm", step));
+        let file =
+            File::create(&path).with_context(|| format!("Could not create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        write_ppm(simulation.grid(), report.as_ref(), &mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
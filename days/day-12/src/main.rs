@@ -1,6 +1,5 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
-use std::collections::HashSet;
 
 use std::fs::File;
 use std::io::{self, BufRead};
@@ -9,37 +8,162 @@ use std::path::Path;
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-#[derive(Debug, Clone)]
-enum NodeType {
-    Small,
-    Large,
-}
+/// Small caves allowed in one system, one bit each in a visited mask
+const MAX_SMALL_CAVES: usize = 64;
 
+/// Caves interned to indices. Small caves also get a bit in the visited mask
 #[derive(Default, Debug)]
-struct Graph<'a> {
-    adjacency: HashMap<&'a str, Vec<&'a str>>,
-    value_map: HashMap<&'a str, NodeType>,
+struct Caves<'a> {
+    names: Vec<&'a str>,
+    indices: HashMap<&'a str, usize>,
+    bits: Vec<Option<u64>>,
+    adjacency: Vec<Vec<usize>>,
 }
 
-impl<'a> Graph<'a> {
-    fn add_node(&mut self, name: &'a str, node_type: NodeType) {
-        self.value_map.entry(name).or_insert(node_type);
+impl<'a> Caves<'a> {
+    fn intern(&mut self, name: &'a str) -> Result<usize> {
+        if let Some(index) = self.indices.get(name) {
+            return Ok(*index);
+        }
+
+        let bit = if name.to_ascii_uppercase() == name {
+            None
+        } else {
+            let small_caves = self.bits.iter().flatten().count();
+            if small_caves == MAX_SMALL_CAVES {
+                anyhow::bail!("More than {} small caves", MAX_SMALL_CAVES);
+            }
+            Some(1 << small_caves)
+        };
+        let index = self.names.len();
+        self.names.push(name);
+        self.indices.insert(name, index);
+        self.bits.push(bit);
+        self.adjacency.push(vec![]);
+        Ok(index)
+    }
+
+    fn from_edges(edges: &[(&'a str, &'a str)]) -> Result<Self> {
+        let mut caves = Self::default();
+        for (node, neighbor) in edges {
+            let node = caves.intern(node)?;
+            let neighbor = caves.intern(neighbor)?;
+            if caves.bits[node].is_none() && caves.bits[neighbor].is_none() {
+                anyhow::bail!(
+                    "Large caves {} and {} are connected, so paths could go on forever",
+                    caves.names[node],
+                    caves.names[neighbor]
+                );
+            }
+            caves.adjacency[node].push(neighbor);
+            caves.adjacency[neighbor].push(node);
+        }
+
+        Ok(caves)
+    }
+
+    fn index(&self, name: &str) -> Result<usize> {
+        self.indices
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("There is no {} cave", name))
     }
+}
+
+/// Counts paths with memoization. Large caves are stepped over, since they never connect to
+/// each other: an edge from one small cave to another counts once per large cave between them
+struct PathCounter {
+    start: usize,
+    end: usize,
+    bits: Vec<u64>,
+    /// Small caves reachable from each small cave, with the number of ways to get there
+    edges: Vec<Vec<(usize, usize)>>,
+    memo: HashMap<(usize, u64, bool), usize>,
+}
+
+impl PathCounter {
+    fn new(caves: &Caves) -> Result<Self> {
+        let small_caves: Vec<usize> = (0..caves.names.len())
+            .filter(|index| caves.bits[*index].is_some())
+            .collect();
+        let slots: HashMap<usize, usize> = small_caves
+            .iter()
+            .enumerate()
+            .map(|(slot, index)| (*index, slot))
+            .collect();
+        let edges = small_caves
+            .iter()
+            .map(|index| {
+                let mut ways: HashMap<usize, usize> = HashMap::new();
+                for neighbor in &caves.adjacency[*index] {
+                    if caves.bits[*neighbor].is_some() {
+                        *ways.entry(slots[neighbor]).or_default() += 1;
+                    } else {
+                        for next in &caves.adjacency[*neighbor] {
+                            *ways.entry(slots[next]).or_default() += 1;
+                        }
+                    }
+                }
+                let mut ways: Vec<_> = ways.into_iter().collect();
+                ways.sort_unstable();
+                ways
+            })
+            .collect();
+        let slot = |name| {
+            let index = caves.index(name)?;
+            slots
+                .get(&index)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("The {} cave must be small", name))
+        };
 
-    fn add_edge(&mut self, name: &'a str, neighbor_name: &'a str) {
-        let adjacency_list = self.adjacency.entry(name).or_insert_with(Vec::new);
-        adjacency_list.push(neighbor_name);
+        Ok(Self {
+            start: slot("start")?,
+            end: slot("end")?,
+            bits: small_caves
+                .iter()
+                .map(|index| caves.bits[*index].unwrap_or_default())
+                .collect(),
+            edges,
+            memo: HashMap::new(),
+        })
     }
 
-    fn neighbors(&self, name: &'a str) -> Vec<&'a str> {
-        self.adjacency.get(name).cloned().unwrap_or_default()
+    /// Paths from `start` to `end` visiting small caves once, except for at most one small cave
+    /// other than `start` visited twice when `allow_twice` is set
+    fn count(&mut self, allow_twice: bool) -> Result<usize> {
+        self.count_from(self.start, self.bits[self.start], !allow_twice)
     }
 
-    fn value_at(&self, name: &'a str) -> NodeType {
-        self.value_map
-            .get(&name)
-            .expect("Couldn't get value for name")
-            .clone()
+    fn count_from(&mut self, node: usize, visited: u64, twice_used: bool) -> Result<usize> {
+        if node == self.end {
+            return Ok(1);
+        }
+        if let Some(count) = self.memo.get(&(node, visited, twice_used)) {
+            return Ok(*count);
+        }
+
+        let mut count: usize = 0;
+        for index in 0..self.edges[node].len() {
+            let (next, ways) = self.edges[node][index];
+            let bit = self.bits[next];
+            let paths = if next == self.start {
+                0
+            } else if visited & bit == 0 {
+                self.count_from(next, visited | bit, twice_used)?
+            } else if !twice_used {
+                self.count_from(next, visited, true)?
+            } else {
+                0
+            };
+            count = paths
+                .checked_mul(ways)
+                .and_then(|paths| count.checked_add(paths))
+                .ok_or_else(|| anyhow::anyhow!("There are too many paths to count"))?;
+        }
+
+        self.memo.insert((node, visited, twice_used), count);
+        Ok(count)
     }
 }
 
@@ -59,95 +183,83 @@ fn parse_line(line: &str) -> Result<(&str, &str)> {
         .ok_or_else(|| anyhow::anyhow!("Could not parse line"))
 }
 
-fn construct_graph<'a>(lines: Vec<(&'a str, &'a str)>) -> Graph<'a> {
-    let mut graph = Graph::default();
-    for (node, neighbor) in lines.into_iter() {
-        graph.add_node(
-            node,
-            if node.to_ascii_uppercase() == node {
-                NodeType::Large
-            } else {
-                NodeType::Small
-            },
-        );
-        graph.add_node(
-            neighbor,
-            if neighbor.to_ascii_uppercase() == neighbor {
-                NodeType::Large
-            } else {
-                NodeType::Small
-            },
-        );
-        graph.add_edge(node, neighbor);
-        graph.add_edge(neighbor, node);
+/// Walks every path one by one, calling `visit` with the caves along it
+fn for_each_path<F>(caves: &Caves, allow_twice: bool, mut visit: F) -> Result<()>
+where
+    F: FnMut(&[usize]),
+{
+    let start = caves.index("start")?;
+    let end = caves.index("end")?;
+    let mut path = vec![start];
+    walk_paths(
+        caves,
+        end,
+        &mut path,
+        caves.bits[start].unwrap_or_default(),
+        !allow_twice,
+        &mut visit,
+    );
+    Ok(())
+}
+
+fn walk_paths<F>(
+    caves: &Caves,
+    end: usize,
+    path: &mut Vec<usize>,
+    visited: u64,
+    twice_used: bool,
+    visit: &mut F,
+) where
+    F: FnMut(&[usize]),
+{
+    let current_node = path[path.len() - 1];
+    if current_node == end {
+        visit(path);
+        return;
     }
 
-    graph
+    for node in &caves.adjacency[current_node] {
+        if *node == path[0] {
+            continue;
+        }
+        let bit = caves.bits[*node].unwrap_or_default();
+        let (visited, twice_used) = if visited & bit == 0 {
+            (visited | bit, twice_used)
+        } else if !twice_used {
+            (visited, true)
+        } else {
+            continue;
+        };
+        path.push(*node);
+        walk_paths(caves, end, path, visited, twice_used, visit);
+        path.pop();
+    }
 }
 
-fn find_paths_for_target<'a>(
-    graph: &Graph<'a>,
-    current_node: &'a str,
-    target: &'a str,
-    visits: &mut HashSet<&'a str>,
-    mut seen_twice: Option<&'a str>,
-) -> usize {
-    if current_node == target {
-        return 1;
-    }
-
-    match graph.value_at(current_node) {
-        NodeType::Small => {
-            if !visits.insert(current_node) {
-                if seen_twice.is_some() || current_node == "start" {
-                    return 0;
-                }
-                seen_twice = Some(current_node);
-            }
-        }
-        NodeType::Large => (),
-    };
+fn parse_caves(lines: &[String]) -> Result<Vec<(&str, &str)>> {
+    lines.iter().map(|line| parse_line(line)).collect()
+}
 
-    let path_sum = graph
-        .neighbors(current_node)
-        .into_iter()
-        .map(|node| find_paths_for_target(graph, node, target, visits, seen_twice))
-        .sum();
+fn count_paths(lines: &[String], allow_twice: bool) -> Result<usize> {
+    let edges = parse_caves(lines)?;
+    let caves = Caves::from_edges(&edges)?;
+    PathCounter::new(&caves)?.count(allow_twice)
+}
 
-    if seen_twice.unwrap_or("") != current_node {
-        visits.remove(current_node);
-    }
-    path_sum
+fn enumerate_paths(lines: &[String], allow_twice: bool) -> Result<usize> {
+    let edges = parse_caves(lines)?;
+    let caves = Caves::from_edges(&edges)?;
+    let mut count = 0;
+    for_each_path(&caves, allow_twice, |_| count += 1)?;
+    Ok(count)
 }
 
 fn part_one(lines: &[String]) -> Result<usize> {
-    let lines: Result<Vec<(&str, &str)>> = lines.iter().map(|line| parse_line(line)).collect();
-    let lines = lines?;
-    let graph = construct_graph(lines);
-    let start = "start";
-    let end = "end";
-    Ok(find_paths_for_target(
-        &graph,
-        start,
-        end,
-        &mut HashSet::new(),
-        Some(""),
-    ))
+    count_paths(lines, false)
 }
 
 fn part_two(lines: &[String]) -> Result<usize> {
-    let lines: Result<Vec<(&str, &str)>> = lines.iter().map(|line| parse_line(line)).collect();
-    let lines = lines?;
-    let graph = construct_graph(lines);
-    let start = "start";
-    let end = "end";
-    Ok(find_paths_for_target(
-        &graph,
-        start,
-        end,
-        &mut HashSet::new(),
-        None,
-    ))
+    count_paths(lines, true)
 }
 
 fn main() -> Result<()> {
@@ -162,5 +274,95 @@ fn main() -> Result<()> {
     assert_eq!(5920, part_one(&input)?);
     assert_eq!(155477, part_two(&input)?);
 
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--enumerate") {
+        println!("{:?}", enumerate_paths(&input, false)?);
+        println!("{:?}", enumerate_paths(&input, true)?);
+    }
+    if args.iter().any(|arg| arg == "--list") {
+        let edges = parse_caves(&input)?;
+        let caves = Caves::from_edges(&edges)?;
+        for_each_path(&caves, true, |path| {
+            let names: Vec<&str> = path.iter().map(|node| caves.names[*node]).collect();
+            println!("{}", names.join(","));
+        })?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(edges: &str) -> Vec<String> {
+        edges.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn examples() -> [(Vec<String>, usize, usize); 3] {
+        [
+            (lines("start-A start-b A-c A-b b-d A-end b-end"), 10, 36),
+            (
+                lines("dc-end HN-start start-kj dc-start dc-HN LN-dc HN-end kj-sa kj-HN kj-dc"),
+                19,
+                103,
+            ),
+            (
+                lines(
+                    "fs-end he-DX fs-he start-DX pj-DX end-zg zg-sl zg-pj pj-he RW-he fs-DX \
+                     pj-RW zg-RW start-pj he-WI zg-he pj-fs start-RW",
+                ),
+                226,
+                3509,
+            ),
+        ]
+    }
+
+    #[test]
+    fn counts_example_paths() {
+        for (lines, once, twice) in examples() {
+            assert_eq!(part_one(&lines).unwrap(), once);
+            assert_eq!(part_two(&lines).unwrap(), twice);
+            assert_eq!(enumerate_paths(&lines, false).unwrap(), once);
+            assert_eq!(enumerate_paths(&lines, true).unwrap(), twice);
+        }
+    }
+
+    #[test]
+    fn lists_paths() {
+        let lines = lines("start-A A-b A-end");
+        let edges = parse_caves(&lines).unwrap();
+        let caves = Caves::from_edges(&edges).unwrap();
+        let mut paths = vec![];
+        for_each_path(&caves, true, |path| {
+            let names: Vec<&str> = path.iter().map(|node| caves.names[*node]).collect();
+            paths.push(names.join(","));
+        })
+        .unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            ["start,A,b,A,b,A,end", "start,A,b,A,end", "start,A,end"]
+        );
+    }
+
+    #[test]
+    fn reports_counts_too_large_for_usize() {
+        let mut edges = vec![];
+        for large in 0..200 {
+            edges.push(format!("start-L{}", large));
+            edges.push(format!("L{}-end", large));
+            edges.extend((0..12).map(|small| format!("L{}-s{}", large, small)));
+        }
+        for count in [part_one(&edges), part_two(&edges)] {
+            assert!(count.unwrap_err().to_string().contains("too many paths"));
+        }
+    }
+
+    #[test]
+    fn rejects_unbounded_systems() {
+        assert!(part_one(&lines("start-A A-B B-end")).is_err());
+        assert!(part_one(&lines("start-a a-b")).is_err());
+        assert!(part_one(&lines("START-a a-end")).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use geometry::{BoundingBox, Point};
 use itertools::Itertools;
 use std::collections::HashSet;
 use std::fmt::Display;
@@ -10,7 +11,7 @@ use std::str::FromStr;
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-type Coord = (usize, usize);
+type Coord = Point<usize>;
 
 #[derive(Debug, Clone)]
 enum FoldInstruction {
@@ -22,7 +23,7 @@ impl FromStr for FoldInstruction {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let last_word = s.split_ascii_whitespace().rev().next();
+        let last_word = s.split_ascii_whitespace().next_back();
         let last_word =
             last_word.ok_or_else(|| anyhow::anyhow!("Could not get line for fold instruction"))?;
         let (direction, line) = last_word
@@ -49,10 +50,10 @@ impl Grid {
         match instruction {
             FoldInstruction::Horizontal(new_y_size) => {
                 let (points_greater_than_fold, points_less_than_fold): (Vec<Coord>, Vec<Coord>) =
-                    self.points.iter().partition(|(_, y)| new_y_size < y);
+                    self.points.iter().partition(|point| *new_y_size < point.y);
                 let adjusted_points: Vec<Coord> = points_greater_than_fold
                     .into_iter()
-                    .map(|(x, y)| (x, 2 * new_y_size - y))
+                    .map(|point| Point::new(point.x, 2 * new_y_size - point.y))
                     .collect();
                 self.points = HashSet::from_iter(
                     points_less_than_fold
@@ -63,10 +64,10 @@ impl Grid {
             }
             FoldInstruction::Vertical(new_x_size) => {
                 let (points_greater_than_fold, points_less_than_fold): (Vec<Coord>, Vec<Coord>) =
-                    self.points.iter().partition(|(x, _)| new_x_size < x);
+                    self.points.iter().partition(|point| *new_x_size < point.x);
                 let adjusted_points: Vec<Coord> = points_greater_than_fold
                     .into_iter()
-                    .map(|(x, y)| (2 * new_x_size - x, y))
+                    .map(|point| Point::new(2 * new_x_size - point.x, point.y))
                     .collect();
                 self.points = HashSet::from_iter(
                     points_less_than_fold
@@ -93,26 +94,12 @@ impl FromIterator<Coord> for Grid {
 
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (max_x, _) = self
-            .points
-            .iter()
-            .max_by(|first, second| (first.0).cmp(&second.0))
-            .expect("Could not determine max x value");
-        let (_, max_y) = self
-            .points
-            .iter()
-            .max_by(|first, second| (first.1).cmp(&second.1))
-            .expect("Could not determine max y value");
-
-        for y in 0..=*max_y {
-            let points: HashSet<Coord> = HashSet::from_iter(
-                self.points
-                    .iter()
-                    .filter(|(_, point_y)| y == *point_y)
-                    .cloned(),
-            );
-            for x in 0..=*max_x {
-                if points.contains(&(x, y)) {
+        let bounds = BoundingBox::from_points(self.points.iter().copied())
+            .expect("Could not determine grid bounds");
+
+        for y in 0..=bounds.max.y {
+            for x in 0..=bounds.max.x {
+                if self.points.contains(&Point::new(x, y)) {
                     f.write_str("X")?;
                 } else {
                     f.write_str(".")?;
@@ -140,7 +127,7 @@ fn parse_coords(line: &str) -> Result<Coord> {
     let (x, y) = line
         .split_once(',')
         .ok_or_else(|| anyhow::anyhow!("Could not split coord line"))?;
-    Ok((str::parse::<usize>(x)?, str::parse::<usize>(y)?))
+    Ok(Point::new(str::parse::<usize>(x)?, str::parse::<usize>(y)?))
 }
 
 fn partition_instructions<'a>(lines: &'a [String]) -> (Vec<&'a str>, Vec<&'a str>) {
@@ -164,7 +151,7 @@ fn partition_instructions<'a>(lines: &'a [String]) -> (Vec<&'a str>, Vec<&'a str
         })
         .collect::<Vec<Vec<&'a str>>>();
     (
-        lines.get(0).cloned().expect("Could not get coords"),
+        lines.first().cloned().expect("Could not get coords"),
         lines
             .get(1)
             .cloned()
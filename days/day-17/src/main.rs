@@ -1,95 +1,37 @@
 use anyhow::{Context, Result};
+use geometry::BoundingBox;
 use itertools::Itertools;
 
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::ops::{Add, AddAssign, SubAssign};
 use std::path::Path;
 
 const CURRENT_FILE: &str = file!();
 const INPUT_FILE_PATH: &str = "../data/input.txt";
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl From<(i64, i64)> for Point {
-    fn from((x, y): (i64, i64)) -> Self {
-        Self { x, y }
-    }
-}
-
-impl Add<Velocity> for Point {
-    type Output = Self;
-
-    fn add(self, rhs: Velocity) -> Self::Output {
-        Self::Output {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
-
-impl AddAssign<Velocity> for Point {
-    fn add_assign(&mut self, other: Velocity) {
-        *self = Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        };
-    }
-}
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Velocity {
-    x: i64,
-    y: i64,
-}
+type Point = geometry::Point<i64>;
+type Velocity = geometry::Point<i64>;
 
-impl From<(i64, i64)> for Velocity {
-    fn from((x, y): (i64, i64)) -> Self {
-        Self { x, y }
-    }
+/// The target area, with `min` at the bottom left since the probe falls towards negative y
+struct Target {
+    bounds: BoundingBox<i64>,
 }
 
-impl<V: Into<Velocity>> SubAssign<V> for Velocity {
-    fn sub_assign(&mut self, rhs: V) {
-        let rhs = rhs.into();
-        *self = Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        };
-    }
-}
-
-struct BoundingBox {
-    top_left: Point,
-    bottom_right: Point,
-}
-
-impl BoundingBox {
+impl Target {
     fn from_coords(xleft: i64, xright: i64, ytop: i64, ybottom: i64) -> Self {
         Self {
-            top_left: Point { x: xleft, y: ytop },
-            bottom_right: Point {
-                x: xright,
-                y: ybottom,
-            },
+            bounds: BoundingBox::new(Point::new(xleft, ytop), Point::new(xright, ybottom)),
         }
     }
 
     fn has_collision(&self, probe: &Probe) -> bool {
-        let point = probe.position();
-
-        (self.top_left.x <= point.x && point.x <= self.bottom_right.x)
-            && (self.bottom_right.y <= point.y && point.y <= self.top_left.y)
+        self.bounds.contains(probe.position())
     }
 
     fn overshot(&self, probe: &Probe) -> bool {
         let point = probe.position();
 
-        point.x > self.bottom_right.x || point.y < self.bottom_right.y
+        point.x > self.bounds.max.x || point.y < self.bounds.min.y
     }
 }
 
@@ -109,7 +51,7 @@ impl Probe {
 
     fn step(&mut self) -> (i64, i64) {
         self.position += self.velocity;
-        self.velocity -= (1, 1);
+        self.velocity -= Velocity::new(1, 1);
         self.velocity.x = self.velocity.x.clamp(0, i64::MAX);
         (self.position.x, self.position.y)
     }
@@ -130,7 +72,7 @@ where
         .collect()
 }
 
-fn parse_line(line: &str) -> Result<BoundingBox> {
+fn parse_line(line: &str) -> Result<Target> {
     let (_, coords) = line
         .split_once(":")
         .ok_or_else(|| anyhow::anyhow!("Could not split coords"))?;
@@ -149,7 +91,7 @@ fn parse_line(line: &str) -> Result<BoundingBox> {
     let (min_y, max_y) = y_range
         .split_once("..")
         .ok_or_else(|| anyhow::anyhow!("Could not get max or min x coords"))?;
-    Ok(BoundingBox::from_coords(
+    Ok(Target::from_coords(
         str::parse::<i64>(min_x)?,
         str::parse::<i64>(max_x)?,
         str::parse::<i64>(max_y)?,